@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Instant;
+
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use crate::compilation::Compilation;
+use crate::configuration::{Configuration, Emit};
+use crate::diagnostic::Diagnostic;
+use crate::error::Error;
+use crate::invocation;
+use crate::process::{self, Limits};
+
+/// Asynchronous counterpart of [`crate::FileOps`]
+pub trait AsyncFileOps {
+    /// Create temporary file
+    /// # Errors
+    /// When temporary file creation fails
+    fn create_temp_exact(
+        filename: &str,
+    ) -> impl Future<Output = std::io::Result<(tempfile::TempDir, PathBuf, File)>> + Send;
+
+    /// Create file, including its missing parent directories
+    /// # Errors
+    /// When directory or file creation fails
+    fn create_file_all(path: &Path) -> impl Future<Output = std::io::Result<File>> + Send;
+
+    /// Writes content to path, the content is flushed once this completes
+    /// # Errors
+    /// When writing fails
+    fn write_all(file: &mut File, data: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send;
+
+    /// Reads content from path
+    /// # Errors
+    /// When reading fails
+    fn read_file(path: &Path) -> impl Future<Output = std::io::Result<Vec<u8>>> + Send;
+}
+
+pub enum AsyncTempFS {}
+
+impl AsyncFileOps for AsyncTempFS {
+    async fn create_temp_exact(
+        filename: &str,
+    ) -> std::io::Result<(tempfile::TempDir, PathBuf, File)> {
+        let temp_dir = tokio::task::spawn_blocking(tempfile::TempDir::new).await??;
+        let path = PathBuf::from(temp_dir.path()).join(filename);
+        let file = File::create(&path).await?;
+        Ok((temp_dir, path, file))
+    }
+
+    async fn create_file_all(path: &Path) -> std::io::Result<File> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        File::create(path).await
+    }
+
+    async fn write_all(file: &mut File, data: &[u8]) -> std::io::Result<()> {
+        file.write_all(data).await?;
+        file.flush().await
+    }
+
+    async fn read_file(path: &Path) -> std::io::Result<Vec<u8>> {
+        tokio::fs::read(path).await
+    }
+}
+
+pub type AsyncCompiler = AsyncAbstractCompiler<AsyncTempFS>;
+
+pub struct AsyncAbstractCompiler<FS: AsyncFileOps> {
+    _fs: PhantomData<FS>,
+}
+
+impl<FS: AsyncFileOps> AsyncAbstractCompiler<FS> {
+    /// Compiles like [`crate::AbstractCompiler::compile`], without blocking
+    /// the executor. Dropping the future kills `rustc` and the processes it
+    /// spawned.
+    ///
+    /// # Errors
+    /// - If the configuration is inconsistent
+    /// - If using the host's file system fails.
+    /// - If compilation fails
+    /// - If compilation exceeds the configured limits
+    /// - If the module cannot be rendered as text when requested
+    /// - If the module lacks one of the required exports
+    /// - If no source map can be generated for the module when requested
+    pub async fn compile(configuration: &Configuration) -> Result<Compilation, Error> {
+        invocation::compile(&Tokio::<FS>(PhantomData), configuration).await
+    }
+}
+
+/// Host of the asynchronous compiler, which keeps blocking work off the
+/// executor
+struct Tokio<FS: AsyncFileOps>(PhantomData<FS>);
+
+impl<FS: AsyncFileOps> invocation::Host for Tokio<FS> {
+    type File = File;
+
+    fn create_temp_exact(
+        filename: &str,
+    ) -> impl Future<Output = std::io::Result<(tempfile::TempDir, PathBuf, File)>> {
+        FS::create_temp_exact(filename)
+    }
+
+    fn create_file_all(path: &Path) -> impl Future<Output = std::io::Result<File>> {
+        FS::create_file_all(path)
+    }
+
+    fn write_all(file: &mut File, data: &[u8]) -> impl Future<Output = std::io::Result<()>> {
+        FS::write_all(file, data)
+    }
+
+    fn read_file(path: &Path) -> impl Future<Output = std::io::Result<Vec<u8>>> {
+        FS::read_file(path)
+    }
+
+    async fn run(&self, command: std::process::Command, limits: &Limits) -> Result<Output, Error> {
+        run(command, limits).await
+    }
+
+    async fn finish(
+        configuration: &Configuration,
+        directories: Vec<tempfile::TempDir>,
+        wasm: Vec<u8>,
+        diagnostics: Vec<Diagnostic>,
+        artifacts: BTreeMap<Emit, Vec<u8>>,
+    ) -> Result<Compilation, Error> {
+        let configuration = configuration.clone();
+        tokio::task::spawn_blocking(move || {
+            drop(directories);
+            Compilation::new(&configuration, wasm, diagnostics, artifacts)
+        })
+        .await
+        .map_err(|error| Error::IO(error.into()))?
+    }
+}
+
+/// Kills the process group of a child once dropped, unless the child
+/// completed before that
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(id) = self.0 {
+            process::kill_group(id);
+        }
+    }
+}
+
+/// Asynchronous counterpart of [`process::run`], where cancelling amounts to
+/// dropping the future
+async fn run(mut command: std::process::Command, limits: &Limits) -> Result<Output, Error> {
+    let start = Instant::now();
+    process::prepare(&mut command, limits)?;
+
+    let mut command = tokio::process::Command::from(command);
+    command.kill_on_drop(true);
+    let child = command.spawn().map_err(Error::IO)?;
+    let mut group = ProcessGroup(child.id());
+
+    let output = child.wait_with_output();
+    let output = match limits.selected_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, output)
+            .await
+            .map_err(|_| Error::TimedOut(start.elapsed()))?,
+        None => output.await,
+    }
+    .map_err(Error::IO)?;
+    group.0 = None;
+
+    process::finish(output, limits)
+}
@@ -0,0 +1,108 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use jobserver::Client as JobserverClient;
+
+use crate::error::Error;
+
+/// How many compilations of a batch run at the same time
+#[derive(Clone, Debug, Default)]
+pub struct Parallelism {
+    jobs: Option<NonZeroUsize>,
+    jobserver: Option<JobserverClient>,
+}
+
+impl Parallelism {
+    /// As many compilations as there are CPUs available
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximal number of compilations at the same time
+    #[must_use]
+    pub fn jobs(self, jobs: NonZeroUsize) -> Self {
+        Self {
+            jobs: Some(jobs),
+            ..self
+        }
+    }
+
+    /// Cooperates with a GNU make jobserver, e.g. the one of the `make` or
+    /// `cargo` that runs this process: besides the one this process holds,
+    /// every compilation that runs at the same time takes a token. The
+    /// jobserver is passed on to `rustc`, whose additional codegen threads
+    /// take tokens as well.
+    #[must_use]
+    pub fn jobserver(self, client: JobserverClient) -> Self {
+        Self {
+            jobserver: Some(client),
+            ..self
+        }
+    }
+
+    /// The configured number of jobs, or the available parallelism otherwise
+    #[must_use]
+    pub fn selected_jobs(&self) -> NonZeroUsize {
+        self.jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN))
+    }
+
+    #[must_use]
+    pub fn selected_jobserver(&self) -> Option<&JobserverClient> {
+        self.jobserver.as_ref()
+    }
+}
+
+/// Runs `task` for every item, with at most the selected number of jobs at
+/// the same time, and yields the results in the order of the items. The
+/// task is handed the jobserver, if any, to pass on to the processes it
+/// spawns so they take their additional threads from it as well.
+pub(crate) fn run<I, T, F>(items: &[I], parallelism: &Parallelism, task: F) -> Vec<Result<T, Error>>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I, Option<&JobserverClient>) -> Result<T, Error> + Sync,
+{
+    let workers = parallelism.selected_jobs().get().min(items.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results = std::thread::scope(|scope| {
+        let handles = (0..workers)
+            .map(|worker| {
+                let (next, task) = (&next, &task);
+                scope.spawn(move || {
+                    let mut results = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(item) = items.get(index) else {
+                            break results;
+                        };
+                        // The first worker runs on the token this process holds
+                        let token = match parallelism.selected_jobserver() {
+                            Some(client) if worker > 0 => Some(client.acquire()),
+                            _ => None,
+                        };
+                        let result = match token {
+                            Some(Err(error)) => Err(Error::IO(error)),
+                            Some(Ok(_)) | None => task(item, parallelism.selected_jobserver()),
+                        };
+                        results.push((index, result));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
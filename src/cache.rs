@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Write};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use sha2::{Digest, Sha256};
+
+use crate::FileOps;
+use crate::compilation::Compilation;
+use crate::configuration::{Configuration, Rlib};
+use crate::error::{Error, VersionError};
+use crate::toolchain::Toolchain;
+
+/// Content address of a compilation: the hash of the full configuration
+/// together with the exact version of the toolchain's `rustc` that compiles it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Feeds everything that is hashed into a SHA-256 digest, which, unlike the
+/// std hashers, is stable across processes and thus usable on disk.
+struct DigestHasher(Sha256);
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// The digest so far, truncated, for `Hash` impls that consult it. Keys
+    /// are derived from the full digest instead.
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let mut prefix = [0; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(prefix)
+    }
+}
+
+impl CacheKey {
+    pub(crate) fn new(configuration: &Configuration, rustc_version: &str) -> Self {
+        let mut hasher = DigestHasher(Sha256::new());
+        rustc_version.hash(&mut hasher);
+        configuration.hash(&mut hasher);
+
+        // Libraries on disk are part of the input, not just their location
+        for (_, rlib) in configuration.dependencies().externs() {
+            if let Rlib::Path(path) = rlib {
+                std::fs::read(path).ok().hash(&mut hasher);
+            }
+        }
+
+        let digest = hasher.0.finalize();
+        let hex = digest.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+        Self(hex)
+    }
+}
+
+struct Entry {
+    compilation: Compilation,
+    created: Instant,
+    last_used: Instant,
+}
+
+/// Cache of compilations, kept in memory and optionally on disk.
+///
+/// Entries are evicted once they exceed the maximal age, and the least
+/// recently used entries are evicted once the cache exceeds its maximal size.
+pub struct Cache {
+    memory: Mutex<HashMap<CacheKey, Entry>>,
+    directory: Option<PathBuf>,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    rustc_versions: Mutex<HashMap<Toolchain, String>>,
+}
+
+impl Cache {
+    /// Cache that only lives as long as this value
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self {
+            memory: Mutex::new(HashMap::new()),
+            directory: None,
+            max_size: None,
+            max_age: None,
+            rustc_versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache that is also persisted in `directory`, shared across processes.
+    ///
+    /// Entries are files named after their key, other files in `directory`
+    /// are left alone. Each file of an entry is written to a temporary file
+    /// first and then renamed into place, the module last, so other processes
+    /// never read an entry that is only partially written.
+    #[must_use]
+    pub fn on_disk(directory: PathBuf) -> Self {
+        Self {
+            directory: Some(directory),
+            ..Self::in_memory()
+        }
+    }
+
+    /// Maximal total size in bytes of the cached modules, per layer
+    #[must_use]
+    pub fn max_size(self, bytes: u64) -> Self {
+        Self {
+            max_size: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Maximal age of a cached module
+    #[must_use]
+    pub fn max_age(self, age: Duration) -> Self {
+        Self {
+            max_age: Some(age),
+            ..self
+        }
+    }
+
+    /// The key under which the compilation of `configuration` is cached
+    ///
+    /// # Errors
+    /// When the `rustc` version cannot be determined
+    pub fn key(&self, configuration: &Configuration) -> Result<CacheKey, VersionError> {
+        let toolchain = configuration.toolchain();
+        let mut rustc_versions = self
+            .rustc_versions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if !rustc_versions.contains_key(toolchain) {
+            let rustc_version = toolchain.version_output()?;
+            rustc_versions.insert(toolchain.clone(), rustc_version);
+        }
+        let rustc_version = &rustc_versions[toolchain];
+        Ok(CacheKey::new(configuration, rustc_version))
+    }
+
+    /// Removes the compilation of `configuration` from the cache
+    ///
+    /// # Errors
+    /// - When the `rustc` version cannot be determined
+    /// - When removing the entry from disk fails
+    pub fn invalidate(&self, configuration: &Configuration) -> Result<(), Error> {
+        let key = self.key(configuration).map_err(Error::Version)?;
+        self.remove(&key).map_err(Error::IO)
+    }
+
+    /// Removes a single entry from the cache
+    ///
+    /// # Errors
+    /// When removing the entry from disk fails
+    pub fn remove(&self, key: &CacheKey) -> std::io::Result<()> {
+        self.lock().remove(key);
+        if let Some(directory) = &self.directory
+            && directory.exists()
+        {
+            // The module, its diagnostics and its auxiliary artifacts
+            let paths = entries_in(directory)?
+                .into_iter()
+                .filter(|path| path.file_stem().is_some_and(|stem| *stem == *key.0))
+                .collect::<Vec<_>>();
+            remove_all(&paths)?;
+        }
+        Ok(())
+    }
+
+    /// Removes all entries from the cache
+    ///
+    /// # Errors
+    /// When removing the entries from disk fails
+    pub fn clear(&self) -> std::io::Result<()> {
+        self.lock().clear();
+        if let Some(directory) = &self.directory
+            && directory.exists()
+        {
+            remove_all(&entries_in(directory)?)?;
+        }
+        Ok(())
+    }
+
+    /// Looks up a compilation, in memory first and on disk second
+    pub(crate) fn get<FS: FileOps>(
+        &self,
+        key: &CacheKey,
+        configuration: &Configuration,
+    ) -> Option<Compilation> {
+        let now = Instant::now();
+        {
+            let mut memory = self.lock();
+            if let Some(entry) = memory.get_mut(key) {
+                if self.max_age.is_none_or(|age| now - entry.created <= age) {
+                    entry.last_used = now;
+                    return Some(entry.compilation.clone());
+                }
+                memory.remove(key);
+            }
+        }
+
+        let directory = self.directory.as_ref()?;
+        let [wasm_path, diagnostics_path] = entry_paths(directory, key);
+        let modified = std::fs::metadata(&wasm_path)
+            .and_then(|m| m.modified())
+            .ok()?;
+        if !self.is_fresh(modified) {
+            return None;
+        }
+        let wasm = FS::read_file(&wasm_path).ok()?;
+        let diagnostics = FS::read_file(&diagnostics_path).ok()?;
+        let diagnostics = serde_json::from_slice(&diagnostics).ok()?;
+        let mut artifacts = BTreeMap::new();
+        for kind in configuration.emit() {
+            let artifact = FS::read_file(&kind.path_next_to(&wasm_path)).ok()?;
+            artifacts.insert(*kind, artifact);
+        }
+
+        let compilation = Compilation::new(configuration, wasm, diagnostics, artifacts).ok()?;
+        self.insert_in_memory(key.clone(), compilation.clone());
+        Some(compilation)
+    }
+
+    /// Stores a compilation in memory and on disk, evicting old entries
+    pub(crate) fn insert<FS: FileOps>(
+        &self,
+        key: CacheKey,
+        compilation: &Compilation,
+    ) -> std::io::Result<()> {
+        if let Some(directory) = &self.directory {
+            let [wasm_path, diagnostics_path] = entry_paths(directory, &key);
+            std::fs::create_dir_all(directory)?;
+            let diagnostics = serde_json::to_vec(&compilation.diagnostics)?;
+            write_atomically::<FS>(&diagnostics_path, &diagnostics)?;
+            for (kind, artifact) in &compilation.artifacts {
+                write_atomically::<FS>(&kind.path_next_to(&wasm_path), artifact)?;
+            }
+            // The module marks the entry as complete, so it comes last
+            write_atomically::<FS>(&wasm_path, &compilation.wasm)?;
+            self.evict_on_disk()?;
+        }
+
+        self.insert_in_memory(key, compilation.clone());
+        Ok(())
+    }
+
+    fn insert_in_memory(&self, key: CacheKey, compilation: Compilation) {
+        let now = Instant::now();
+        let mut memory = self.lock();
+        memory.insert(
+            key,
+            Entry {
+                compilation,
+                created: now,
+                last_used: now,
+            },
+        );
+
+        // Evict entries that are too old, then the least recently used ones
+        if let Some(age) = self.max_age {
+            memory.retain(|_, entry| now - entry.created <= age);
+        }
+        if let Some(max_size) = self.max_size {
+            let mut by_use = memory
+                .iter()
+                .map(|(key, entry)| (entry.last_used, key.clone(), entry.compilation.wasm.len()))
+                .collect::<Vec<_>>();
+            by_use.sort_by_key(|(last_used, _, _)| *last_used);
+            let mut size = by_use.iter().map(|(_, _, len)| *len as u64).sum::<u64>();
+            for (_, key, len) in by_use {
+                if size <= max_size {
+                    break;
+                }
+                memory.remove(&key);
+                size -= len as u64;
+            }
+        }
+    }
+
+    fn is_fresh(&self, modified: SystemTime) -> bool {
+        self.max_age.is_none_or(|age| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|elapsed| elapsed <= age)
+        })
+    }
+
+    /// Evicts entries on disk that are too old, then the least recently
+    /// written ones until the directory fits within the maximal size. The
+    /// files of an entry are evicted together.
+    fn evict_on_disk(&self) -> std::io::Result<()> {
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+
+        // The files of each entry by its key, with their total size and the
+        // time the entry was last written
+        let mut entries = BTreeMap::<String, (SystemTime, u64, Vec<PathBuf>)>::new();
+        for path in entries_in(directory)? {
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                // Removed meanwhile by another cache on the same directory
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let modified = metadata.modified()?;
+            let entry = entries
+                .entry(stem.to_owned())
+                .or_insert((modified, 0, vec![]));
+            entry.0 = entry.0.max(modified);
+            entry.1 += metadata.len();
+            entry.2.push(path);
+        }
+
+        let (fresh, stale): (Vec<_>, Vec<_>) = entries
+            .into_values()
+            .partition(|(modified, _, _)| self.is_fresh(*modified));
+        for (_, _, paths) in stale {
+            remove_all(&paths)?;
+        }
+
+        if let Some(max_size) = self.max_size {
+            let mut fresh = fresh;
+            fresh.sort_by_key(|(modified, _, _)| *modified);
+            let mut size = fresh.iter().map(|(_, len, _)| *len).sum::<u64>();
+            for (_, len, paths) in fresh {
+                if size <= max_size {
+                    break;
+                }
+                remove_all(&paths)?;
+                size -= len;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<CacheKey, Entry>> {
+        self.memory
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// The module and diagnostics of an entry on disk, its auxiliary artifacts
+/// are stored next to the module
+fn entry_paths(directory: &Path, key: &CacheKey) -> [PathBuf; 2] {
+    [
+        directory.join(format!("{key}.wasm")),
+        directory.join(format!("{key}.json")),
+    ]
+}
+
+/// The files of the entries in `directory`: those named after a key, which
+/// leaves other files and directories alone
+fn entries_in(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = vec![];
+    for file in std::fs::read_dir(directory)? {
+        let file = file?;
+        let path = file.path();
+        let named_after_key = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| {
+                stem.len() == 64
+                    && stem
+                        .bytes()
+                        .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+            });
+        if named_after_key && file.file_type()?.is_file() {
+            entries.push(path);
+        }
+    }
+    Ok(entries)
+}
+
+/// Removes the files, those already removed (e.g. by another cache on the
+/// same directory) are skipped
+fn remove_all(paths: &[PathBuf]) -> std::io::Result<()> {
+    for path in paths {
+        match std::fs::remove_file(path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Writes `data` to a temporary file next to `path`, which is then renamed
+/// to `path` in one step
+fn write_atomically<FS: FileOps>(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut temporary = tempfile::NamedTempFile::new_in(directory)?;
+    FS::write_all(temporary.as_file_mut(), data)?;
+    temporary.persist(path).map_err(|error| error.error)?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::configuration::{ModuleTree, Target, is_nested};
+use crate::diagnostic::{Diagnostic, SerdeJsonError};
+use crate::error::Error;
+use crate::process::Limits;
+use crate::toolchain::Toolchain;
+
+/// Where the Cargo workspace to build lives
+#[derive(Clone, Debug)]
+pub enum Workspace {
+    /// An existing workspace on the host, built in place
+    Directory(PathBuf),
+    /// A workspace written to a temporary directory before building. The
+    /// files are keyed by their path relative to the root `Cargo.toml`, and
+    /// may include locally vendored dependencies.
+    InMemory { manifest: String, files: ModuleTree },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum CargoProfile {
+    #[default]
+    Dev,
+    Release,
+    Custom(String),
+}
+
+impl CargoProfile {
+    fn include_in(&self, command: &mut Command) {
+        match self {
+            CargoProfile::Dev => {}
+            CargoProfile::Release => {
+                command.arg("--release");
+            }
+            CargoProfile::Custom(profile) => {
+                command.arg("--profile").arg(profile);
+            }
+        }
+    }
+}
+
+/// Configuration to build a Cargo package into a WebAssembly module, the
+/// package is expected to declare a `cdylib` library target.
+#[derive(Clone, Debug)]
+pub struct CargoConfiguration {
+    pub(crate) workspace: Workspace,
+    pub(crate) package: Option<String>,
+    pub(crate) target: Target,
+    pub(crate) profile: CargoProfile,
+    pub(crate) features: Vec<String>,
+    pub(crate) no_default_features: bool,
+    pub(crate) toolchain: Toolchain,
+    pub(crate) limits: Limits,
+}
+
+impl CargoConfiguration {
+    #[must_use]
+    pub fn new(workspace: Workspace) -> Self {
+        Self {
+            workspace,
+            package: None,
+            target: Target::default(),
+            profile: CargoProfile::default(),
+            features: vec![],
+            no_default_features: false,
+            toolchain: Toolchain::default(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Selects the package to build within the workspace
+    #[must_use]
+    pub fn package(self, package: &str) -> Self {
+        Self {
+            package: Some(package.into()),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn target(self, target: Target) -> Self {
+        Self { target, ..self }
+    }
+
+    #[must_use]
+    pub fn profile(self, profile: CargoProfile) -> Self {
+        Self { profile, ..self }
+    }
+
+    #[must_use]
+    pub fn feature(mut self, feature: &str) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    #[must_use]
+    pub fn no_default_features(self) -> Self {
+        Self {
+            no_default_features: true,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn toolchain(self, toolchain: Toolchain) -> Self {
+        Self { toolchain, ..self }
+    }
+
+    /// Selects the limits imposed on `cargo`, defaults to none
+    #[must_use]
+    pub fn limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    #[must_use]
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// Checks the settings before `cargo` is invoked
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if let Workspace::InMemory { files, .. } = &self.workspace {
+            for path in files.keys() {
+                if !is_nested(path) || path == Path::new("Cargo.toml") {
+                    return Err(Error::InvalidModulePath(path.clone()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn as_command(&self, workspace_dir: &Path) -> Command {
+        let mut command = self.toolchain.cargo(self.target);
+        command.arg("build");
+        // Only use dependencies that are vendored or already fetched
+        command.arg("--offline");
+        // Report diagnostics and artifacts as JSON
+        command.arg("--message-format=json");
+        command
+            .arg("--manifest-path")
+            .arg(workspace_dir.join("Cargo.toml"));
+        command.arg(format!("--target={}", self.target.triple()));
+        self.profile.include_in(&mut command);
+        if let Some(package) = &self.package {
+            command.arg("--package").arg(package);
+        }
+        if !self.features.is_empty() {
+            command.arg("--features").arg(self.features.join(","));
+        }
+        if self.no_default_features {
+            command.arg("--no-default-features");
+        }
+
+        command
+    }
+}
+
+#[derive(Deserialize)]
+struct ArtifactTarget {
+    crate_types: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason")]
+enum Message {
+    #[serde(rename = "compiler-message")]
+    Diagnostic { message: Diagnostic },
+    #[serde(rename = "compiler-artifact")]
+    Artifact {
+        package_id: String,
+        target: ArtifactTarget,
+        filenames: Vec<PathBuf>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Diagnostics and `cdylib` artifacts reported by `cargo --message-format=json`,
+/// the artifacts are keyed by the ID of the package which produced them
+pub(crate) struct Messages {
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    pub(crate) cdylibs: Vec<(String, PathBuf)>,
+}
+
+/// Parses the stdout of a `cargo build --message-format=json` invocation.
+pub(crate) fn parse(stdout: &[u8]) -> Result<Messages, SerdeJsonError> {
+    let mut messages = Messages {
+        diagnostics: vec![],
+        cdylibs: vec![],
+    };
+
+    for line in stdout.split(|byte| *byte == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice(line)? {
+            Message::Diagnostic { message } => messages.diagnostics.push(message),
+            Message::Artifact {
+                package_id,
+                target,
+                filenames,
+            } => {
+                if target.crate_types.iter().any(|kind| kind == "cdylib") {
+                    let wasm = filenames
+                        .into_iter()
+                        .find(|file| file.extension().is_some_and(|ext| ext == "wasm"));
+                    if let Some(wasm) = wasm {
+                        messages.cdylibs.push((package_id, wasm));
+                    }
+                }
+            }
+            Message::Other => {}
+        }
+    }
+
+    Ok(messages)
+}
+
+/// The name of the package designated by a `package_id`, which is either
+/// `name version (source)` or, since Cargo 1.77, a package ID spec such as
+/// `path+file:///greeter#0.1.0` or `path+file:///vendor/helper#helper@0.1.0`
+/// whose name defaults to the last segment of the URL
+pub(crate) fn package_name(package_id: &str) -> &str {
+    if let Some((name, _)) = package_id.split_once(' ') {
+        return name;
+    }
+    let (url, fragment) = package_id.split_once('#').unwrap_or((package_id, ""));
+    if let Some((name, _)) = fragment.split_once('@') {
+        return name;
+    }
+    let path = url.split_once('?').map_or(url, |(path, _)| path);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_names() {
+        for (package_id, name) in [
+            ("greeter 0.1.0 (path+file:///tmp/greeter)", "greeter"),
+            ("path+file:///tmp/greeter#0.1.0", "greeter"),
+            (
+                "path+file:///tmp/vendor/helper#my-helper@0.1.0",
+                "my-helper",
+            ),
+            (
+                "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0",
+                "serde",
+            ),
+            (
+                "git+https://example.com/repo/greeter?branch=main#0.1.0",
+                "greeter",
+            ),
+        ] {
+            assert_eq!(package_name(package_id), name);
+        }
+    }
+
+    #[test]
+    fn artifacts_are_keyed_by_package() {
+        let stdout = br#"{"reason":"compiler-artifact","package_id":"path+file:///tmp/greeter#0.1.0","target":{"name":"renamed","kind":["cdylib"],"crate_types":["cdylib"]},"filenames":["/tmp/target/renamed.wasm"]}
+{"reason":"build-finished","success":true}
+"#;
+
+        let messages = parse(stdout).unwrap();
+
+        assert_eq!(
+            messages.cdylibs,
+            vec![(
+                "path+file:///tmp/greeter#0.1.0".to_string(),
+                PathBuf::from("/tmp/target/renamed.wasm")
+            )]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use crate::configuration::{Configuration, CrateType, Emit, Wat};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::error::{Error, ModuleInfoError, SymbolicationError};
+use crate::module_info::ModuleInfo;
+use crate::source_map;
+use crate::symbolication::Symbolizer;
+#[cfg(feature = "runtime")]
+use crate::{
+    error::RuntimeError,
+    runtime::{CompiledModule, ExecutionLimits},
+};
+
+/// Outcome of a successful compilation
+#[derive(Clone, Debug)]
+pub struct Compilation {
+    pub(crate) wasm: Vec<u8>,
+    pub(crate) crate_type: CrateType,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    pub(crate) artifacts: BTreeMap<Emit, Vec<u8>>,
+    pub(crate) wat: Option<String>,
+    pub(crate) source_map: Option<String>,
+}
+
+impl Compilation {
+    /// The compilation of `configuration` into `wasm`, with a source map and
+    /// rendered as text when the configuration asks for it
+    ///
+    /// # Errors
+    /// - If the module lacks one of the required exports
+    /// - If no source map can be generated for the module
+    /// - If the module cannot be rendered as text
+    pub(crate) fn new(
+        configuration: &Configuration,
+        mut wasm: Vec<u8>,
+        diagnostics: Vec<Diagnostic>,
+        artifacts: BTreeMap<Emit, Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let crate_type = configuration.crate_type();
+        if crate_type != CrateType::Rlib {
+            configuration.required_exports().verify(&wasm)?;
+        }
+
+        let source_map = match crate_type {
+            CrateType::Cdylib | CrateType::Bin => source_map::attach(configuration, &mut wasm)?,
+            CrateType::Rlib => None,
+        };
+
+        let wat = match (configuration.wat(), crate_type) {
+            (Wat::Enabled, CrateType::Cdylib | CrateType::Bin) => Some(print_wat(&wasm)?),
+            (Wat::Enabled, CrateType::Rlib) | (Wat::Disabled, _) => None,
+        };
+
+        Ok(Self {
+            wasm,
+            crate_type,
+            diagnostics,
+            artifacts,
+            wat,
+            source_map,
+        })
+    }
+
+    /// The produced artifact, a WebAssembly module unless the crate type
+    /// is [`CrateType::Rlib`], in which case it is the library archive
+    #[must_use]
+    pub fn wasm(&self) -> &[u8] {
+        &self.wasm
+    }
+
+    /// Consumes the compilation, yielding the produced artifact
+    #[must_use]
+    pub fn into_wasm(self) -> Vec<u8> {
+        self.wasm
+    }
+
+    /// The kind of artifact that was produced
+    #[must_use]
+    pub fn crate_type(&self) -> CrateType {
+        self.crate_type
+    }
+
+    /// Inspects the produced module: its exports, imports, memories,
+    /// tables, globals and sections
+    ///
+    /// # Errors
+    /// When the produced artifact is not a module, e.g. for [`CrateType::Rlib`]
+    pub fn module_info(&self) -> Result<ModuleInfo, ModuleInfoError> {
+        ModuleInfo::parse(&self.wasm)
+    }
+
+    /// Maps code offsets of the produced module, e.g. of a trap, back to the
+    /// source, which requires [`crate::configuration::Debugging::Enabled`]
+    ///
+    /// # Errors
+    /// When the produced artifact is not a module or lacks debug information
+    pub fn symbolizer(&self) -> Result<Symbolizer, SymbolicationError> {
+        Symbolizer::new(&self.wasm)
+    }
+
+    /// Prepares the produced module for execution with wasmtime
+    ///
+    /// # Errors
+    /// When the produced artifact is not a module, e.g. for [`CrateType::Rlib`]
+    #[cfg(feature = "runtime")]
+    pub fn compiled_module(&self, limits: ExecutionLimits) -> Result<CompiledModule, RuntimeError> {
+        CompiledModule::new(&self.wasm, limits)
+    }
+
+    /// The module in the WebAssembly text format, if requested and the
+    /// produced artifact is a module
+    #[must_use]
+    pub fn wat(&self) -> Option<&str> {
+        self.wat.as_deref()
+    }
+
+    /// The source map of the module in the revision 3 JSON format, if
+    /// requested and the produced artifact is a module
+    #[must_use]
+    pub fn source_map(&self) -> Option<&str> {
+        self.source_map.as_deref()
+    }
+
+    /// The contents of an auxiliary artifact, if it was requested
+    #[must_use]
+    pub fn artifact(&self, kind: Emit) -> Option<&[u8]> {
+        self.artifacts.get(&kind).map(Vec::as_slice)
+    }
+
+    /// The contents of every requested auxiliary artifact, keyed by kind
+    #[must_use]
+    pub fn artifacts(&self) -> &BTreeMap<Emit, Vec<u8>> {
+        &self.artifacts
+    }
+
+    /// All diagnostics `rustc` emitted while compiling
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The diagnostics `rustc` emitted at warning level
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == Level::Warning)
+    }
+}
+
+/// Renders a module in the WebAssembly text format, named after its name
+/// section and with instructions folded into s-expressions
+fn print_wat(wasm: &[u8]) -> Result<String, Error> {
+    let mut wat = String::new();
+    wasmprinter::Config::new()
+        .fold_instructions(true)
+        .print(wasm, &mut wasmprinter::PrintFmtWrite(&mut wat))
+        .map_err(|error| Error::WatUnprintable(error.into()))?;
+    Ok(wat)
+}
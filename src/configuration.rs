@@ -1,12 +1,25 @@
-use std::{path::Path, process::Command};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::{Error, ExportError, MemoryLayoutError};
+use crate::module_info::{ExternType, FunctionType, ModuleInfo};
+use crate::process::Limits;
+use crate::toolchain::Toolchain;
 
 pub type Source = String;
 
+/// Files of the crate besides its root, keyed by their path relative to the
+/// directory of the crate root, e.g. `math.rs` or `util/mod.rs`
+pub type ModuleTree = BTreeMap<PathBuf, Vec<u8>>;
+
 trait IncludeInCommand {
     fn include_in(&self, command: &mut Command);
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Profile {
     O0,
     O1,
@@ -27,7 +40,7 @@ impl IncludeInCommand for Profile {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum StackSize {
     Unspecified,
     Configured(u32),
@@ -44,7 +57,7 @@ impl IncludeInCommand for StackSize {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Debugging {
     Enabled,
     Disabled,
@@ -61,16 +74,621 @@ impl IncludeInCommand for Debugging {
     }
 }
 
-#[derive(Clone, Debug)]
+/// The WebAssembly target triple to compile for.
+///
+/// Besides the default crate type, see [`Target::default_crate_type`], the
+/// target selects the linker arguments through `rustc` itself: a `cdylib` is
+/// linked with `--no-entry` and exports its `#[no_mangle]` functions, a WASI
+/// `bin` gets a `_start` calling `main`, and `wasm32-wasip2` is linked into a
+/// component. No further linker arguments are passed unless configured, e.g.
+/// through [`MemoryLayout`] or [`StackSize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Hash)]
+pub enum Target {
+    #[default]
+    Wasm32UnknownUnknown,
+    Wasm32Wasip1,
+    Wasm32Wasip2,
+    Wasm32v1None,
+}
+
+impl Target {
+    /// The target triple as understood by `rustc`
+    #[must_use]
+    pub fn triple(&self) -> &'static str {
+        match self {
+            Target::Wasm32UnknownUnknown => "wasm32-unknown-unknown",
+            Target::Wasm32Wasip1 => "wasm32-wasip1",
+            Target::Wasm32Wasip2 => "wasm32-wasip2",
+            Target::Wasm32v1None => "wasm32v1-none",
+        }
+    }
+
+    /// WASI targets produce command modules with a `main` entry point,
+    /// the others produce libraries exposing their exported functions.
+    #[must_use]
+    pub fn default_crate_type(&self) -> CrateType {
+        match self {
+            Target::Wasm32UnknownUnknown | Target::Wasm32v1None => CrateType::Cdylib,
+            Target::Wasm32Wasip1 | Target::Wasm32Wasip2 => CrateType::Bin,
+        }
+    }
+}
+
+impl IncludeInCommand for Target {
+    fn include_in(&self, command: &mut Command) {
+        command.arg(format!("--target={}", self.triple()));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CrateType {
+    /// Library module exposing its exported functions, no `main` required
+    Cdylib,
+    /// Command module with a `main` entry point
+    Bin,
+    /// Rust library archive, to be linked by later compilations
+    Rlib,
+}
+
+impl IncludeInCommand for CrateType {
+    fn include_in(&self, command: &mut Command) {
+        let arg = match self {
+            CrateType::Cdylib => "--crate-type=cdylib",
+            CrateType::Bin => "--crate-type=bin",
+            CrateType::Rlib => "--crate-type=rlib",
+        };
+
+        command.arg(arg);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TargetFeature {
+    Simd128,
+    RelaxedSimd,
+    BulkMemory,
+    SignExt,
+    Multivalue,
+    ReferenceTypes,
+    TailCall,
+    Atomics,
+}
+
+impl TargetFeature {
+    /// The feature name as understood by `-Ctarget-feature`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetFeature::Simd128 => "simd128",
+            TargetFeature::RelaxedSimd => "relaxed-simd",
+            TargetFeature::BulkMemory => "bulk-memory",
+            TargetFeature::SignExt => "sign-ext",
+            TargetFeature::Multivalue => "multivalue",
+            TargetFeature::ReferenceTypes => "reference-types",
+            TargetFeature::TailCall => "tail-call",
+            TargetFeature::Atomics => "atomics",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Hash)]
+pub enum TargetCpu {
+    /// Leave the choice to `rustc`, which picks `generic`
+    #[default]
+    Unspecified,
+    Mvp,
+    Generic,
+    BleedingEdge,
+}
+
+impl IncludeInCommand for TargetCpu {
+    fn include_in(&self, command: &mut Command) {
+        let arg = match self {
+            TargetCpu::Unspecified => return,
+            TargetCpu::Mvp => "-Ctarget-cpu=mvp",
+            TargetCpu::Generic => "-Ctarget-cpu=generic",
+            TargetCpu::BleedingEdge => "-Ctarget-cpu=bleeding-edge",
+        };
+
+        command.arg(arg);
+    }
+}
+
+/// CPU preset together with features switched on or off on top of it
+#[derive(Clone, Debug, PartialEq, Eq, Default, Hash)]
+pub struct TargetFeatures {
+    cpu: TargetCpu,
+    enabled: BTreeSet<TargetFeature>,
+    disabled: BTreeSet<TargetFeature>,
+}
+
+impl TargetFeatures {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn cpu(self, cpu: TargetCpu) -> Self {
+        Self { cpu, ..self }
+    }
+
+    /// Switches a feature on, overriding an earlier `disable`
+    #[must_use]
+    pub fn enable(mut self, feature: TargetFeature) -> Self {
+        self.disabled.remove(&feature);
+        self.enabled.insert(feature);
+        self
+    }
+
+    /// Switches a feature off, overriding an earlier `enable`
+    #[must_use]
+    pub fn disable(mut self, feature: TargetFeature) -> Self {
+        self.enabled.remove(&feature);
+        self.disabled.insert(feature);
+        self
+    }
+
+    #[must_use]
+    pub fn selected_cpu(&self) -> &TargetCpu {
+        &self.cpu
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self, feature: TargetFeature) -> bool {
+        self.enabled.contains(&feature)
+    }
+
+    #[must_use]
+    pub fn is_disabled(&self, feature: TargetFeature) -> bool {
+        self.disabled.contains(&feature)
+    }
+}
+
+impl IncludeInCommand for TargetFeatures {
+    fn include_in(&self, command: &mut Command) {
+        self.cpu.include_in(command);
+
+        let enabled = self
+            .enabled
+            .iter()
+            .map(|feature| format!("+{}", feature.name()));
+        let disabled = self
+            .disabled
+            .iter()
+            .map(|feature| format!("-{}", feature.name()));
+        let features = enabled.chain(disabled).collect::<Vec<_>>();
+
+        if !features.is_empty() {
+            command.arg(format!("-Ctarget-feature={}", features.join(",")));
+        }
+    }
+}
+
+/// Size of a WebAssembly page in bytes
+pub const PAGE_SIZE: u64 = 65536;
+
+/// Largest linear memory a `wasm32` module can address in bytes
+pub const MAX_MEMORY_SIZE: u64 = 1 << 32;
+
+/// Linker settings describing the linear memory of the module.
+/// Sizes and addresses are expressed in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Hash)]
+pub struct MemoryLayout {
+    initial_memory: Option<u64>,
+    max_memory: Option<u64>,
+    import_memory: bool,
+    export_memory: bool,
+    shared_memory: bool,
+    global_base: Option<u64>,
+}
+
+impl MemoryLayout {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initial size of the memory, must be a multiple of [`PAGE_SIZE`]
+    #[must_use]
+    pub fn initial_memory(self, bytes: u64) -> Self {
+        Self {
+            initial_memory: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Maximum size of the memory, must be a multiple of [`PAGE_SIZE`]
+    #[must_use]
+    pub fn max_memory(self, bytes: u64) -> Self {
+        Self {
+            max_memory: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Import the memory from the host (as `env.memory`)
+    #[must_use]
+    pub fn import_memory(self) -> Self {
+        Self {
+            import_memory: true,
+            ..self
+        }
+    }
+
+    /// Export the memory, also when it is imported
+    #[must_use]
+    pub fn export_memory(self) -> Self {
+        Self {
+            export_memory: true,
+            ..self
+        }
+    }
+
+    /// Mark the memory as shared, requires a maximum and the `atomics` feature
+    #[must_use]
+    pub fn shared_memory(self) -> Self {
+        Self {
+            shared_memory: true,
+            ..self
+        }
+    }
+
+    /// Address where global data starts
+    #[must_use]
+    pub fn global_base(self, address: u64) -> Self {
+        Self {
+            global_base: Some(address),
+            ..self
+        }
+    }
+
+    /// Checks the settings against each other and against the features
+    /// they rely on.
+    ///
+    /// # Errors
+    /// When the settings cannot be satisfied by the linker
+    pub fn validate(&self, target_features: &TargetFeatures) -> Result<(), MemoryLayoutError> {
+        let sizes = [
+            ("initial memory", self.initial_memory),
+            ("max memory", self.max_memory),
+        ];
+
+        for (setting, bytes) in sizes {
+            let Some(bytes) = bytes else { continue };
+            if bytes % PAGE_SIZE != 0 {
+                return Err(MemoryLayoutError::NotPageAligned(setting, bytes));
+            }
+            if bytes > MAX_MEMORY_SIZE {
+                return Err(MemoryLayoutError::ExceedsAddressSpace(setting, bytes));
+            }
+        }
+
+        if let (Some(initial), Some(max)) = (self.initial_memory, self.max_memory)
+            && initial > max
+        {
+            return Err(MemoryLayoutError::InitialExceedsMax(initial, max));
+        }
+
+        if let (Some(base), Some(initial)) = (self.global_base, self.initial_memory)
+            && base >= initial
+        {
+            return Err(MemoryLayoutError::GlobalBaseOutsideMemory(base, initial));
+        }
+
+        if self.shared_memory {
+            if self.max_memory.is_none() {
+                return Err(MemoryLayoutError::SharedWithoutMax);
+            }
+            let atomics = target_features.is_enabled(TargetFeature::Atomics)
+                || (target_features.selected_cpu() == &TargetCpu::BleedingEdge
+                    && !target_features.is_disabled(TargetFeature::Atomics));
+            if !atomics {
+                return Err(MemoryLayoutError::SharedWithoutAtomics);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IncludeInCommand for MemoryLayout {
+    fn include_in(&self, command: &mut Command) {
+        if let Some(bytes) = self.initial_memory {
+            command.arg(format!("-Clink-arg=--initial-memory={bytes}"));
+        }
+        if let Some(bytes) = self.max_memory {
+            command.arg(format!("-Clink-arg=--max-memory={bytes}"));
+        }
+        if self.import_memory {
+            command.arg("-Clink-arg=--import-memory");
+        }
+        if self.export_memory {
+            command.arg("-Clink-arg=--export-memory");
+        }
+        if self.shared_memory {
+            command.arg("-Clink-arg=--shared-memory");
+        }
+        if let Some(address) = self.global_base {
+            command.arg(format!("-Clink-arg=--global-base={address}"));
+        }
+    }
+}
+
+/// A pre-built library compiled for the same wasm target
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Rlib {
+    /// Library archive already present on the host
+    Path(PathBuf),
+    /// Library archive held in memory, written to a temporary directory
+    Bytes(Vec<u8>),
+}
+
+/// Crates made available to the compiled source through `--extern`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dependencies {
+    externs: Vec<(String, Rlib)>,
+    search_paths: Vec<PathBuf>,
+}
+
+impl Dependencies {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the library at `path` available as crate `name`
+    #[must_use]
+    pub fn extern_path(mut self, name: &str, path: PathBuf) -> Self {
+        self.externs.push((name.into(), Rlib::Path(path)));
+        self
+    }
+
+    /// Makes the in-memory library available as crate `name`
+    #[must_use]
+    pub fn extern_bytes(mut self, name: &str, rlib: Vec<u8>) -> Self {
+        self.externs.push((name.into(), Rlib::Bytes(rlib)));
+        self
+    }
+
+    /// Adds a directory to search for transitive dependencies
+    #[must_use]
+    pub fn search_path(mut self, directory: PathBuf) -> Self {
+        self.search_paths.push(directory);
+        self
+    }
+
+    #[must_use]
+    pub fn externs(&self) -> &[(String, Rlib)] {
+        &self.externs
+    }
+
+    #[must_use]
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+}
+
+impl IncludeInCommand for Dependencies {
+    fn include_in(&self, command: &mut Command) {
+        for directory in &self.search_paths {
+            let mut search_path = std::ffi::OsString::from("dependency=");
+            search_path.push(directory);
+            command.arg("-L").arg(search_path);
+        }
+    }
+}
+
+/// Whether the compilation includes the WebAssembly text format of the module
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Hash)]
+pub enum Wat {
+    Enabled,
+    #[default]
+    Disabled,
+}
+
+/// Whether the compilation includes a source map of the module, which maps its
+/// code back to the source through the DWARF of [`Debugging::Enabled`]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Hash)]
+pub enum SourceMap {
+    #[default]
+    Disabled,
+    /// Only returned alongside the module, through `Compilation::source_map`
+    Separate,
+    /// Also referred to from the module's `sourceMappingURL` section by this URL
+    Linked(String),
+    /// Also embedded in the module's `sourceMappingURL` section as a data URL
+    Embedded,
+}
+
+/// Auxiliary artifact `rustc` can emit besides the linked module.
+///
+/// `rustc` only writes [`Emit::LlvmIr`], [`Emit::LlvmBc`], [`Emit::Asm`] and
+/// [`Emit::Obj`] to a single file when there is one codegen unit, so
+/// requesting any of them compiles with `-Ccodegen-units=1`. That applies
+/// to the module as well, which may thus differ from the one compiled without
+/// these artifacts: it is optimized as a whole, and takes longer to compile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Emit {
+    LlvmIr,
+    LlvmBc,
+    Asm,
+    Mir,
+    Obj,
+    DepInfo,
+    Metadata,
+}
+
+impl Emit {
+    /// The name of the kind as understood by `rustc --emit`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Emit::LlvmIr => "llvm-ir",
+            Emit::LlvmBc => "llvm-bc",
+            Emit::Asm => "asm",
+            Emit::Mir => "mir",
+            Emit::Obj => "obj",
+            Emit::DepInfo => "dep-info",
+            Emit::Metadata => "metadata",
+        }
+    }
+
+    /// The extension `rustc` gives the artifact by default
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Emit::LlvmIr => "ll",
+            Emit::LlvmBc => "bc",
+            Emit::Asm => "s",
+            Emit::Mir => "mir",
+            Emit::Obj => "o",
+            Emit::DepInfo => "d",
+            Emit::Metadata => "rmeta",
+        }
+    }
+
+    /// Whether `rustc` produces the artifact per codegen unit
+    fn is_per_codegen_unit(self) -> bool {
+        match self {
+            Emit::LlvmIr | Emit::LlvmBc | Emit::Asm | Emit::Obj => true,
+            Emit::Mir | Emit::DepInfo | Emit::Metadata => false,
+        }
+    }
+
+    /// Where the artifact is emitted when the module is written to `output_path`
+    pub(crate) fn path_next_to(self, output_path: &Path) -> PathBuf {
+        output_path.with_extension(self.extension())
+    }
+}
+
+/// Exports the produced module must have, verified once it is compiled.
+///
+/// Functions that are not `#[no_mangle]` are not exported under their
+/// own name, if exported at all, which this catches at compile time rather
+/// than at instantiation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RequiredExports {
+    exports: Vec<(String, Option<FunctionType>)>,
+}
+
+impl RequiredExports {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires a function exported as `name` with the given signature
+    #[must_use]
+    pub fn function(mut self, name: &str, signature: FunctionType) -> Self {
+        self.exports.push((name.into(), Some(signature)));
+        self
+    }
+
+    /// Requires an export named `name`, of any kind
+    #[must_use]
+    pub fn export(mut self, name: &str) -> Self {
+        self.exports.push((name.into(), None));
+        self
+    }
+
+    /// The required export names, with the signature if it is a function
+    #[must_use]
+    pub fn exports(&self) -> &[(String, Option<FunctionType>)] {
+        &self.exports
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty()
+    }
+
+    /// Checks the module against the required exports
+    pub(crate) fn verify(&self, wasm: &[u8]) -> Result<(), Error> {
+        self.find_violation(wasm)
+            .map_or(Ok(()), |error| Err(Error::RequiredExport(Box::new(error))))
+    }
+
+    fn find_violation(&self, wasm: &[u8]) -> Option<ExportError> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let info = match ModuleInfo::parse(wasm) {
+            Ok(info) => info,
+            Err(error) => return Some(ExportError::Uninspectable(error)),
+        };
+        for (name, signature) in &self.exports {
+            let Some(export) = info.export(name) else {
+                let mangled = info
+                    .function_names
+                    .iter()
+                    .find(|symbol| is_mangled_from(symbol, name))
+                    .cloned();
+                return Some(ExportError::Missing(name.clone(), mangled));
+            };
+            match (signature, &export.ty) {
+                (None, _) => {}
+                (Some(expected), ExternType::Function(found)) if expected == found => {}
+                (Some(expected), found) => {
+                    return Some(ExportError::Mismatched(
+                        name.clone(),
+                        expected.clone(),
+                        found.clone(),
+                    ));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Whether `symbol` is a Rust mangled name (legacy or v0) of an item named
+/// `identifier`, recognized by the length-prefixed identifier it contains
+fn is_mangled_from(symbol: &str, identifier: &str) -> bool {
+    let Some(path) = symbol
+        .strip_prefix("_ZN")
+        .or_else(|| symbol.strip_prefix("_R"))
+    else {
+        return false;
+    };
+
+    // v0 separates the length from identifiers starting with a digit or `_`
+    [
+        format!("{}{identifier}", identifier.len()),
+        format!("{}_{identifier}", identifier.len()),
+    ]
+    .iter()
+    .any(|segment| {
+        // The length must not be the tail of a longer one, e.g. `13fac`
+        path.match_indices(segment.as_str())
+            .any(|(start, _)| !path[..start].ends_with(|previous: char| previous.is_ascii_digit()))
+    })
+}
+
+#[derive(Clone, Debug, Hash)]
 pub struct Configuration {
     pub(crate) profile: Profile,
     pub(crate) debugging: Debugging,
     pub(crate) stack_size: StackSize,
     pub(crate) source: String,
     pub(crate) filename: Filename,
+    pub(crate) target: Target,
+    pub(crate) crate_type: Option<CrateType>,
+    pub(crate) target_features: TargetFeatures,
+    pub(crate) memory_layout: MemoryLayout,
+    pub(crate) modules: ModuleTree,
+    pub(crate) dependencies: Dependencies,
+    pub(crate) toolchain: Toolchain,
+    pub(crate) limits: Limits,
+    pub(crate) emit: BTreeSet<Emit>,
+    pub(crate) wat: Wat,
+    pub(crate) required_exports: RequiredExports,
+    pub(crate) source_map: SourceMap,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
 pub enum Filename {
     Unspecified,
     Configured(String),
@@ -91,11 +709,128 @@ impl Configuration {
     pub fn source(&self) -> &str {
         &self.source
     }
+
+    #[must_use]
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// The configured crate type, or the default of the target otherwise
+    #[must_use]
+    pub fn crate_type(&self) -> CrateType {
+        self.crate_type
+            .unwrap_or_else(|| self.target.default_crate_type())
+    }
+
+    #[must_use]
+    pub fn target_features(&self) -> &TargetFeatures {
+        &self.target_features
+    }
+
+    #[must_use]
+    pub fn memory_layout(&self) -> &MemoryLayout {
+        &self.memory_layout
+    }
+
+    #[must_use]
+    pub fn modules(&self) -> &ModuleTree {
+        &self.modules
+    }
+
+    #[must_use]
+    pub fn dependencies(&self) -> &Dependencies {
+        &self.dependencies
+    }
+
+    #[must_use]
+    pub fn toolchain(&self) -> &Toolchain {
+        &self.toolchain
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    #[must_use]
+    pub fn emit(&self) -> &BTreeSet<Emit> {
+        &self.emit
+    }
+
+    #[must_use]
+    pub fn wat(&self) -> &Wat {
+        &self.wat
+    }
+
+    #[must_use]
+    pub fn required_exports(&self) -> &RequiredExports {
+        &self.required_exports
+    }
+
+    #[must_use]
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    pub(crate) fn file_name(&self) -> &str {
+        match &self.filename {
+            Filename::Unspecified => "rustc-to-wasm-source.rs",
+            Filename::Configured(filename) => filename.as_str(),
+        }
+    }
+}
+
+/// Whether `path` is a non-empty path that stays within its base directory
+pub(crate) fn is_nested(path: &Path) -> bool {
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
 }
 
 impl Configuration {
-    pub(crate) fn as_command(&self, input_path: &Path, output_path: &Path) -> Command {
-        let mut command = Command::new("rustc");
+    /// Checks the settings against each other before `rustc` is invoked
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        self.memory_layout
+            .validate(&self.target_features)
+            .map_err(Error::InvalidMemoryLayout)?;
+
+        // Modules must stay within the crate directory and not replace the root
+        for path in self.modules.keys() {
+            if !is_nested(path) || path == Path::new(self.file_name()) {
+                return Err(Error::InvalidModulePath(path.clone()));
+            }
+        }
+
+        // Extern names must be valid crate names
+        for (name, _) in &self.dependencies.externs {
+            let mut characters = name.chars();
+            let starts_well = characters
+                .next()
+                .is_some_and(|first| first.is_ascii_alphabetic() || first == '_');
+            let continues_well = characters.all(|next| next.is_ascii_alphanumeric() || next == '_');
+            if !starts_well || !continues_well {
+                return Err(Error::InvalidExternName(name.clone()));
+            }
+        }
+
+        // Source maps are derived from the debug information
+        if self.source_map != SourceMap::Disabled && self.debugging == Debugging::Disabled {
+            return Err(Error::SourceMapWithoutDebugging);
+        }
+
+        Ok(())
+    }
+
+    /// Builds the `rustc` invocation, `externs` hold the on-disk location of
+    /// every configured dependency
+    pub(crate) fn as_command(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        externs: &[(&str, PathBuf)],
+    ) -> Command {
+        let mut command = self.toolchain.rustc();
         // Set output path
         command.arg(input_path);
         // Include performance profile
@@ -104,10 +839,41 @@ impl Configuration {
         self.debugging.include_in(&mut command);
         // Include stack-size flag if set in configuration
         self.stack_size.include_in(&mut command);
+        // Include memory layout flags if set in configuration
+        self.memory_layout.include_in(&mut command);
+        // Report diagnostics as JSON, relative to the source directory
+        command.arg("--error-format=json");
+        if let Some(source_dir) = input_path.parent() {
+            let mut remap = std::ffi::OsString::from("--remap-path-prefix=");
+            remap.push(source_dir);
+            remap.push("=");
+            command.arg(remap);
+        }
         // Set wasm target
-        command.arg("--target=wasm32-unknown-unknown");
-        // Allow omitting a `main` function
-        command.arg("--crate-type=cdylib");
+        self.target.include_in(&mut command);
+        // Set crate type, `cdylib` allows omitting a `main` function
+        self.crate_type().include_in(&mut command);
+        // Include target cpu and features if set in configuration
+        self.target_features.include_in(&mut command);
+        // Include dependency search paths and the dependencies themselves
+        self.dependencies.include_in(&mut command);
+        for (name, path) in externs {
+            let mut extern_crate = std::ffi::OsString::from(format!("{name}="));
+            extern_crate.push(path);
+            command.arg("--extern").arg(extern_crate);
+        }
+        // Emit the requested artifacts next to the module, each in one file
+        if !self.emit.is_empty() {
+            command.arg("--emit=link");
+            for kind in &self.emit {
+                let mut emit = std::ffi::OsString::from(format!("--emit={}=", kind.name()));
+                emit.push(kind.path_next_to(output_path));
+                command.arg(emit);
+            }
+            if self.emit.iter().any(|kind| kind.is_per_codegen_unit()) {
+                command.arg("-Ccodegen-units=1");
+            }
+        }
         // Set output path
         command.arg("-o").arg(output_path);
 
@@ -1,4 +1,11 @@
-use crate::configuration::Configuration;
+use std::collections::BTreeSet;
+
+use crate::configuration::{
+    Configuration, CrateType, Dependencies, Emit, MemoryLayout, ModuleTree, RequiredExports,
+    SourceMap, Target, TargetFeatures, Wat,
+};
+use crate::process::Limits;
+use crate::toolchain::Toolchain;
 
 #[derive(Clone, Debug)]
 pub struct ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename> {
@@ -7,6 +14,19 @@ pub struct ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
     stack_size: StackSize,
     source: Source,
     filename: Filename,
+    /* optional settings, these start out with their defaults */
+    target: Target,
+    crate_type: Option<CrateType>,
+    target_features: TargetFeatures,
+    memory_layout: MemoryLayout,
+    modules: ModuleTree,
+    dependencies: Dependencies,
+    toolchain: Toolchain,
+    limits: Limits,
+    emit: BTreeSet<Emit>,
+    wat: Wat,
+    required_exports: RequiredExports,
+    source_map: SourceMap,
 }
 
 impl ConfigurationBuilder<(), (), (), (), ()> {
@@ -18,6 +38,18 @@ impl ConfigurationBuilder<(), (), (), (), ()> {
             stack_size: (),
             source: (),
             filename: (),
+            target: Target::default(),
+            crate_type: None,
+            target_features: TargetFeatures::default(),
+            memory_layout: MemoryLayout::default(),
+            modules: ModuleTree::new(),
+            dependencies: Dependencies::default(),
+            toolchain: Toolchain::default(),
+            limits: Limits::default(),
+            emit: BTreeSet::new(),
+            wat: Wat::default(),
+            required_exports: RequiredExports::default(),
+            source_map: SourceMap::default(),
         }
     }
 }
@@ -39,6 +71,18 @@ impl
             stack_size,
             source,
             filename,
+            target,
+            crate_type,
+            target_features,
+            memory_layout,
+            modules,
+            dependencies,
+            toolchain,
+            limits,
+            emit,
+            wat,
+            required_exports,
+            source_map,
         } = self;
 
         Configuration {
@@ -47,6 +91,18 @@ impl
             stack_size,
             source,
             filename,
+            target,
+            crate_type,
+            target_features,
+            memory_layout,
+            modules,
+            dependencies,
+            toolchain,
+            limits,
+            emit,
+            wat,
+            required_exports,
+            source_map,
         }
     }
 }
@@ -65,6 +121,18 @@ impl<Profile, Debugging, StackSize, Source, Filename>
             stack_size: self.stack_size,
             source: self.source,
             filename: self.filename,
+            target: self.target,
+            crate_type: self.crate_type,
+            target_features: self.target_features,
+            memory_layout: self.memory_layout,
+            modules: self.modules,
+            dependencies: self.dependencies,
+            toolchain: self.toolchain,
+            limits: self.limits,
+            emit: self.emit,
+            wat: self.wat,
+            required_exports: self.required_exports,
+            source_map: self.source_map,
         }
     }
 }
@@ -83,6 +151,18 @@ impl<Profile, Debugging, StackSize, Source, Filename>
             stack_size: self.stack_size,
             source: self.source,
             filename: self.filename,
+            target: self.target,
+            crate_type: self.crate_type,
+            target_features: self.target_features,
+            memory_layout: self.memory_layout,
+            modules: self.modules,
+            dependencies: self.dependencies,
+            toolchain: self.toolchain,
+            limits: self.limits,
+            emit: self.emit,
+            wat: self.wat,
+            required_exports: self.required_exports,
+            source_map: self.source_map,
         }
     }
 }
@@ -101,6 +181,18 @@ impl<Profile, Debugging, StackSize, Source, Filename>
             stack_size,
             source: self.source,
             filename: self.filename,
+            target: self.target,
+            crate_type: self.crate_type,
+            target_features: self.target_features,
+            memory_layout: self.memory_layout,
+            modules: self.modules,
+            dependencies: self.dependencies,
+            toolchain: self.toolchain,
+            limits: self.limits,
+            emit: self.emit,
+            wat: self.wat,
+            required_exports: self.required_exports,
+            source_map: self.source_map,
         }
     }
 }
@@ -119,6 +211,18 @@ impl<Profile, Debugging, StackSize, Source, Filename>
             stack_size: self.stack_size,
             source,
             filename: self.filename,
+            target: self.target,
+            crate_type: self.crate_type,
+            target_features: self.target_features,
+            memory_layout: self.memory_layout,
+            modules: self.modules,
+            dependencies: self.dependencies,
+            toolchain: self.toolchain,
+            limits: self.limits,
+            emit: self.emit,
+            wat: self.wat,
+            required_exports: self.required_exports,
+            source_map: self.source_map,
         }
     }
 }
@@ -137,6 +241,156 @@ impl<Profile, Debugging, StackSize, Source, Filename>
             stack_size: self.stack_size,
             source: self.source,
             filename,
+            target: self.target,
+            crate_type: self.crate_type,
+            target_features: self.target_features,
+            memory_layout: self.memory_layout,
+            modules: self.modules,
+            dependencies: self.dependencies,
+            toolchain: self.toolchain,
+            limits: self.limits,
+            emit: self.emit,
+            wat: self.wat,
+            required_exports: self.required_exports,
+            source_map: self.source_map,
+        }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the WebAssembly target, defaults to `wasm32-unknown-unknown`
+    #[must_use]
+    pub fn target(self, target: Target) -> Self {
+        Self { target, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the crate type, defaults to the one the target expects
+    #[must_use]
+    pub fn crate_type(self, crate_type: CrateType) -> Self {
+        Self {
+            crate_type: Some(crate_type),
+            ..self
+        }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the target cpu and features, defaults to what `rustc` picks
+    #[must_use]
+    pub fn target_features(self, target_features: TargetFeatures) -> Self {
+        Self {
+            target_features,
+            ..self
+        }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the linear memory layout, defaults to what the linker picks
+    #[must_use]
+    pub fn memory_layout(self, memory_layout: MemoryLayout) -> Self {
+        Self {
+            memory_layout,
+            ..self
         }
     }
 }
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Adds files next to the crate root so `mod` declarations and
+    /// `include_str!` resolve, defaults to none
+    #[must_use]
+    pub fn modules(self, modules: ModuleTree) -> Self {
+        Self { modules, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects pre-built libraries to link against, defaults to none
+    #[must_use]
+    pub fn dependencies(self, dependencies: Dependencies) -> Self {
+        Self {
+            dependencies,
+            ..self
+        }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the toolchain that compiles, defaults to `rustc` on the `PATH`
+    #[must_use]
+    pub fn toolchain(self, toolchain: Toolchain) -> Self {
+        Self { toolchain, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the limits imposed on `rustc`, defaults to none
+    #[must_use]
+    pub fn limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects auxiliary artifacts to emit besides the module, defaults to none.
+    /// Some of them change how the module is compiled, see [`Emit`].
+    #[must_use]
+    pub fn emit(self, emit: BTreeSet<Emit>) -> Self {
+        Self { emit, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects whether the module is also rendered as text, defaults to not
+    #[must_use]
+    pub fn wat(self, wat: Wat) -> Self {
+        Self { wat, ..self }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects the exports the module must have, defaults to none
+    #[must_use]
+    pub fn required_exports(self, required_exports: RequiredExports) -> Self {
+        Self {
+            required_exports,
+            ..self
+        }
+    }
+}
+
+impl<Profile, Debugging, StackSize, Source, Filename>
+    ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename>
+{
+    /// Selects whether and how a source map of the module is produced,
+    /// defaults to none
+    #[must_use]
+    pub fn source_map(self, source_map: SourceMap) -> Self {
+        Self { source_map, ..self }
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+pub use serde_json::Error as SerdeJsonError;
+
+/// Severity of a diagnostic as reported by `rustc`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Level {
+    #[serde(rename = "error")]
+    Error,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "note")]
+    Note,
+    #[serde(rename = "help")]
+    Help,
+    #[serde(rename = "failure-note")]
+    FailureNote,
+    #[serde(rename = "error: internal compiler error")]
+    InternalCompilerError,
+}
+
+/// Error code attached to a diagnostic, e.g. `E0425`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Code {
+    pub code: String,
+    pub explanation: Option<String>,
+}
+
+/// Region of a file that a diagnostic refers to.
+///
+/// Lines and columns are 1-based, byte offsets are 0-based. When the span
+/// lies in the submitted source, `file_name` equals the configured filename.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Span {
+    pub file_name: String,
+    pub byte_start: u32,
+    pub byte_end: u32,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub label: Option<String>,
+    pub suggested_replacement: Option<String>,
+}
+
+/// A single structured message emitted by `rustc`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub code: Option<Code>,
+    pub message: String,
+    pub spans: Vec<Span>,
+    pub children: Vec<Diagnostic>,
+    pub rendered: Option<String>,
+}
+
+impl Diagnostic {
+    /// The span the diagnostic is primarily about, if any
+    #[must_use]
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+
+    /// Replacements suggested by this diagnostic and its children, paired
+    /// with the span they would replace
+    #[must_use]
+    pub fn suggestions(&self) -> Vec<(&Span, &str)> {
+        let own = self.spans.iter().filter_map(|span| {
+            let replacement = span.suggested_replacement.as_deref()?;
+            Some((span, replacement))
+        });
+        let children = self.children.iter().flat_map(Diagnostic::suggestions);
+        own.chain(children).collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(rename = "$message_type")]
+    message_type: Option<String>,
+}
+
+/// What a `rustc --error-format=json` invocation reported on stderr
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The lines that are not diagnostics in JSON, e.g. the panic message
+    /// and backtrace of an internal compiler error or raw linker output
+    pub unstructured: String,
+}
+
+/// Parses the stderr of a `rustc --error-format=json` invocation.
+///
+/// Lines that are JSON but not diagnostics (e.g. artifact notifications) are
+/// skipped, lines that are not JSON at all are kept as they are.
+#[must_use]
+pub fn parse(stderr: &[u8]) -> Report {
+    let mut report = Report::default();
+    for line in stderr.split(|byte| *byte == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let diagnostic = match serde_json::from_slice::<Message>(line) {
+            Ok(message) => match message.message_type.as_deref() {
+                None | Some("diagnostic") => serde_json::from_slice(line).ok(),
+                Some(_) => continue,
+            },
+            Err(_) => None,
+        };
+        if let Some(diagnostic) = diagnostic {
+            report.diagnostics.push(diagnostic);
+        } else {
+            report.unstructured.push_str(&String::from_utf8_lossy(line));
+            report.unstructured.push('\n');
+        }
+    }
+    report
+}
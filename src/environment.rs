@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+
+use crate::configuration::Target;
+use crate::error::VersionError;
+use crate::toolchain::{Rustc, Toolchain, ToolchainInfo};
+
+/// A piece of the environment that a compilation needs but is missing
+#[derive(Debug)]
+pub enum EnvironmentProblem {
+    /// The `rustc` of the toolchain cannot be invoked or understood
+    RustcUnavailable(VersionError),
+    /// The standard library of the target is not in the sysroot
+    TargetNotInstalled(Target),
+    /// A linker the target relies on is not shipped with the toolchain
+    LinkerUnavailable(PathBuf),
+}
+
+impl EnvironmentProblem {
+    /// What the user can do to resolve the problem for `toolchain`
+    #[must_use]
+    pub fn remediation(&self, toolchain: &Toolchain) -> String {
+        let (toolchain_flag, toolchain_name) = match toolchain.selected_rustc() {
+            Rustc::Rustup(name) => (format!(" --toolchain {name}"), format!(" {name}")),
+            Rustc::FromPath | Rustc::Binary(_) => (String::new(), String::new()),
+        };
+
+        match self {
+            EnvironmentProblem::RustcUnavailable(_) => match toolchain.selected_rustc() {
+                Rustc::FromPath => {
+                    "Install Rust from https://rustup.rs and make sure `rustc` is on the PATH"
+                        .into()
+                }
+                Rustc::Binary(rustc) => {
+                    format!("Check that {} is an executable rustc", rustc.display())
+                }
+                Rustc::Rustup(name) => {
+                    format!("Install the toolchain: `rustup toolchain install {name}`")
+                }
+            },
+            EnvironmentProblem::TargetNotInstalled(target) => format!(
+                "Install the standard library of the target: `rustup target add {}{toolchain_flag}`",
+                target.triple()
+            ),
+            EnvironmentProblem::LinkerUnavailable(linker) => format!(
+                "{} ships with rustc, reinstall the toolchain: `rustup update{toolchain_name}`",
+                linker.display()
+            ),
+        }
+    }
+}
+
+/// Outcome of checking whether the environment can compile a configuration
+#[derive(Debug)]
+pub struct EnvironmentReport {
+    pub toolchain: Toolchain,
+    /// Description of the toolchain, when its `rustc` could be queried
+    pub toolchain_info: Option<ToolchainInfo>,
+    pub problems: Vec<EnvironmentProblem>,
+}
+
+impl EnvironmentReport {
+    /// Whether nothing is missing to compile
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Every problem paired with what the user can do to resolve it
+    pub fn remediations(&self) -> impl Iterator<Item = (&EnvironmentProblem, String)> {
+        self.problems
+            .iter()
+            .map(|problem| (problem, problem.remediation(&self.toolchain)))
+    }
+
+    pub(crate) fn check(toolchain: &Toolchain, target: Target) -> Self {
+        let toolchain_info = match ToolchainInfo::query(toolchain) {
+            Ok(toolchain_info) => toolchain_info,
+            Err(error) => {
+                return Self {
+                    toolchain: toolchain.clone(),
+                    toolchain_info: None,
+                    problems: vec![EnvironmentProblem::RustcUnavailable(error)],
+                };
+            }
+        };
+
+        let mut problems = vec![];
+
+        if !toolchain_info.has_target(target) {
+            problems.push(EnvironmentProblem::TargetNotInstalled(target));
+        }
+
+        // The linkers are shipped in the tools directory of the host
+        let tools = toolchain_info
+            .sysroot
+            .join("lib")
+            .join("rustlib")
+            .join(&toolchain_info.host)
+            .join("bin");
+        let linkers: &[&str] = match target {
+            Target::Wasm32Wasip2 => &["rust-lld", "wasm-component-ld"],
+            Target::Wasm32UnknownUnknown | Target::Wasm32Wasip1 | Target::Wasm32v1None => {
+                &["rust-lld"]
+            }
+        };
+        for linker in linkers {
+            let linker = tools.join(format!("{linker}{}", std::env::consts::EXE_SUFFIX));
+            if !linker.is_file() {
+                problems.push(EnvironmentProblem::LinkerUnavailable(linker));
+            }
+        }
+
+        Self {
+            toolchain: toolchain.clone(),
+            toolchain_info: Some(toolchain_info),
+            problems,
+        }
+    }
+}
@@ -1,17 +1,175 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::time::Duration;
+
 use thiserror::Error;
 
+use crate::diagnostic::{Diagnostic, Level};
+use crate::module_info::{ExternType, FunctionType};
+
+pub use crate::diagnostic::SerdeJsonError;
+pub use gimli::Error as GimliError;
 pub use semver::Error as SemverError;
 pub use std::io::Error as StdIoError;
 pub use std::process::Output as StdProcessOutput;
 pub use std::string::FromUtf8Error as StdStringFromUtf8Error;
+pub use wasmparser::BinaryReaderError;
 
 #[derive(Debug, Error)]
 /// Error kinds of what can go wrong when a compilation is invoked
 pub enum Error {
     #[error("IO Error: {0}")]
     IO(StdIoError),
-    #[error("No Success: {0:?}")]
-    Unsuccesful(StdProcessOutput),
+    #[error("No Success: {0}")]
+    Unsuccesful(Failure),
+    #[error("Diagnostics unreadable: {0}")]
+    DiagnosticsUnreadable(SerdeJsonError),
+    #[error("Invalid memory layout: {0}")]
+    InvalidMemoryLayout(MemoryLayoutError),
+    #[error("Invalid module path: {0:?}")]
+    InvalidModulePath(PathBuf),
+    #[error("Invalid extern crate name: {0}")]
+    InvalidExternName(String),
+    #[error("No cdylib artifact was produced")]
+    MissingArtifact,
+    #[error("Version Error: {0}")]
+    Version(VersionError),
+    #[error("Timed out after {0:?}")]
+    TimedOut(Duration),
+    #[error("Cancelled after {0:?}")]
+    Cancelled(Duration),
+    #[error("Exceeded the memory limit of {0} bytes")]
+    MemoryLimitExceeded(u64),
+    #[error("Exceeded the CPU time limit of {0:?}")]
+    CpuTimeLimitExceeded(Duration),
+    #[error("Exceeded the file size limit of {0} bytes")]
+    FileSizeLimitExceeded(u64),
+    #[error("Exceeded the limit of {0} processes")]
+    ProcessLimitExceeded(u64),
+    #[error("Resource limits are only supported on Linux")]
+    LimitsUnsupported,
+    #[error("WAT printing failed: {0}")]
+    WatUnprintable(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Required export: {0}")]
+    RequiredExport(Box<ExportError>),
+    #[error("Source maps require `Debugging::Enabled`")]
+    SourceMapWithoutDebugging,
+    #[error("Source map unavailable: {0}")]
+    SourceMapUnavailable(SymbolicationError),
+}
+
+/// A compilation that `rustc` (or `cargo`) reported as failed
+#[derive(Debug)]
+pub struct Failure {
+    pub status: ExitStatus,
+    pub diagnostics: Vec<Diagnostic>,
+    /// What was reported besides the diagnostics, e.g. the panic message of an
+    /// internal compiler error or raw linker output
+    pub unstructured: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "compilation {}", self.status)?;
+        let errors = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == Level::Error);
+        for error in errors {
+            write!(f, "\nerror: {}", error.message)?;
+        }
+        if !self.unstructured.is_empty() {
+            write!(f, "\n{}", self.unstructured.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+/// Error kinds of what can be inconsistent in a memory layout
+pub enum MemoryLayoutError {
+    #[error("{0} of {1} bytes is not a multiple of the page size")]
+    NotPageAligned(&'static str, u64),
+    #[error("{0} of {1} bytes exceeds the 32-bit address space")]
+    ExceedsAddressSpace(&'static str, u64),
+    #[error("initial memory of {0} bytes exceeds max memory of {1} bytes")]
+    InitialExceedsMax(u64, u64),
+    #[error("global base {0} lies outside the initial memory of {1} bytes")]
+    GlobalBaseOutsideMemory(u64, u64),
+    #[error("shared memory requires a max memory")]
+    SharedWithoutMax,
+    #[error("shared memory requires the `atomics` target feature")]
+    SharedWithoutAtomics,
+}
+
+#[derive(Debug, Error)]
+/// Error kinds of how a module can fall short of its required exports
+pub enum ExportError {
+    #[error("{0} is not exported{hint}", hint = mangled_hint(.1.as_deref()))]
+    Missing(String, Option<String>),
+    #[error("{0} is exported as {2:?}, expected a function {1:?}")]
+    Mismatched(String, FunctionType, ExternType),
+    #[error("module cannot be inspected: {0}")]
+    Uninspectable(ModuleInfoError),
+}
+
+fn mangled_hint(mangled: Option<&str>) -> String {
+    match mangled {
+        Some(symbol) => {
+            format!(", but the module contains {symbol}, is `#[no_mangle]` missing?")
+        }
+        None => String::new(),
+    }
+}
+
+#[derive(Debug, Error)]
+/// Error kinds of what can go wrong when a module is inspected
+pub enum ModuleInfoError {
+    #[error("Module unreadable: {0}")]
+    Unreadable(BinaryReaderError),
+    #[error("Components cannot be inspected as a module")]
+    Component,
+    #[error("Type {0} is not a defined function type")]
+    UnknownFunctionType(u32),
+    #[error("Export {0} refers to an undefined item")]
+    UnknownItem(String),
+}
+
+#[derive(Debug, Error)]
+/// Error kinds of what can go wrong when code offsets are mapped to sources
+pub enum SymbolicationError {
+    #[error("Module unreadable: {0}")]
+    Unreadable(BinaryReaderError),
+    #[error("Module carries no debug information, it requires `Debugging::Enabled`")]
+    NoDebugInfo,
+    #[error("Debug information unreadable: {0}")]
+    Dwarf(GimliError),
+    #[error("Offset {0} lies outside the code of the module")]
+    OutsideCode(usize),
+}
+
+#[cfg(feature = "runtime")]
+#[derive(Debug, Error)]
+/// Error kinds of what can go wrong when a compiled module is executed
+pub enum RuntimeError {
+    #[error("Module not executable: {0}")]
+    Module(wasmtime::Error),
+    #[error("Linking failed: {0}")]
+    Link(wasmtime::Error),
+    #[error("No exported function {0}")]
+    UnknownExport(String),
+    #[error("Export {0} has another signature: {1}")]
+    Signature(String, wasmtime::Error),
+    /// The trap, with the module offsets of its backtrace, innermost first
+    #[error("Trapped: {0}")]
+    Trap(wasmtime::Trap, Vec<usize>),
+    #[error("Host function failed: {0}")]
+    Host(wasmtime::Error),
+    #[error("IO Error: {0}")]
+    IO(StdIoError),
+    #[error("Invalid preopened file path: {0:?}")]
+    InvalidPreopenPath(PathBuf),
 }
 
 #[derive(Debug, Error)]
@@ -27,4 +185,6 @@ pub enum VersionError {
     RegexNoMatch(String),
     #[error("Version parse failed: {0}")]
     VersionParseFailed(SemverError),
+    #[error("Missing field in verbose version: {0}")]
+    MissingField(&'static str),
 }
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::process::{Command, Output};
+use std::task::{Context, Poll, Waker};
+
+use crate::compilation::Compilation;
+use crate::configuration::{Configuration, Emit, Rlib};
+use crate::diagnostic::{self, Diagnostic};
+use crate::error::{Error, Failure};
+use crate::process::Limits;
+
+/// What differs between the blocking and the asynchronous compiler: how
+/// files are written and read, how `rustc` is spawned and waited for, and
+/// where the work after it completes runs
+pub(crate) trait Host {
+    type File;
+
+    fn create_temp_exact(
+        filename: &str,
+    ) -> impl Future<Output = std::io::Result<(tempfile::TempDir, PathBuf, Self::File)>>;
+
+    fn create_file_all(path: &Path) -> impl Future<Output = std::io::Result<Self::File>>;
+
+    fn write_all(file: &mut Self::File, data: &[u8]) -> impl Future<Output = std::io::Result<()>>;
+
+    fn read_file(path: &Path) -> impl Future<Output = std::io::Result<Vec<u8>>>;
+
+    fn run(&self, command: Command, limits: &Limits)
+    -> impl Future<Output = Result<Output, Error>>;
+
+    /// Removes the temporary directories and builds the compilation, which
+    /// renders the module as text or maps it to its sources when requested
+    fn finish(
+        configuration: &Configuration,
+        directories: Vec<tempfile::TempDir>,
+        wasm: Vec<u8>,
+        diagnostics: Vec<Diagnostic>,
+        artifacts: BTreeMap<Emit, Vec<u8>>,
+    ) -> impl Future<Output = Result<Compilation, Error>>;
+}
+
+/// Compiles the configuration, the steps all hosts share
+pub(crate) async fn compile<H: Host>(
+    host: &H,
+    configuration: &Configuration,
+) -> Result<Compilation, Error> {
+    configuration.validate()?;
+
+    let (source_parent_dir, input_path, mut input_source) =
+        H::create_temp_exact(configuration.file_name())
+            .await
+            .map_err(Error::IO)?;
+
+    let (out_parent_dir, output_path, _output_wasm) =
+        H::create_temp_exact("rustc-to-wasm-out.wasm")
+            .await
+            .map_err(Error::IO)?;
+
+    // Write into temp file
+    H::write_all(&mut input_source, configuration.source().as_bytes())
+        .await
+        .map_err(Error::IO)?;
+
+    // Write the other files of the crate next to the crate root
+    for (path, contents) in configuration.modules() {
+        let mut module = H::create_file_all(&source_parent_dir.path().join(path))
+            .await
+            .map_err(Error::IO)?;
+        H::write_all(&mut module, contents)
+            .await
+            .map_err(Error::IO)?;
+    }
+
+    // Write in-memory dependencies to disk, each in its own directory
+    let mut directories = vec![source_parent_dir, out_parent_dir];
+    let mut externs = vec![];
+    for (name, rlib) in configuration.dependencies().externs() {
+        match rlib {
+            Rlib::Path(path) => externs.push((name.as_str(), path.clone())),
+            Rlib::Bytes(bytes) => {
+                let (extern_dir, extern_path, mut extern_file) =
+                    H::create_temp_exact(&format!("lib{name}.rlib"))
+                        .await
+                        .map_err(Error::IO)?;
+                H::write_all(&mut extern_file, bytes)
+                    .await
+                    .map_err(Error::IO)?;
+                directories.push(extern_dir);
+                externs.push((name.as_str(), extern_path));
+            }
+        }
+    }
+
+    let command = configuration.as_command(&input_path, &output_path, &externs);
+    let output = host.run(command, configuration.limits()).await?;
+
+    let diagnostic::Report {
+        diagnostics,
+        unstructured,
+    } = diagnostic::parse(&output.stderr);
+
+    if !output.status.success() {
+        return Err(Error::Unsuccesful(Failure {
+            status: output.status,
+            diagnostics,
+            unstructured,
+        }));
+    }
+
+    // Read from temp file
+    let output_content = H::read_file(&output_path).await.map_err(Error::IO)?;
+    let mut artifacts = BTreeMap::new();
+    for kind in configuration.emit() {
+        let artifact = H::read_file(&kind.path_next_to(&output_path))
+            .await
+            .map_err(Error::IO)?;
+        artifacts.insert(*kind, artifact);
+    }
+
+    H::finish(
+        configuration,
+        directories,
+        output_content,
+        diagnostics,
+        artifacts,
+    )
+    .await
+}
+
+/// Runs a future whose steps all complete as soon as they are polled, as the
+/// ones of a blocking host do
+pub(crate) fn complete<T>(future: impl Future<Output = T>) -> T {
+    let mut future = pin!(future);
+    match future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("blocking steps complete once polled"),
+    }
+}
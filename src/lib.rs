@@ -1,19 +1,42 @@
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 use std::path::PathBuf;
-use std::process::Command;
 
 /* re-export the semver version */
 pub use semver::Version;
 
 use ctreg::regex;
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod batch;
+pub mod cache;
+pub mod cargo;
+pub mod compilation;
 pub mod configuration;
 pub mod configuration_builder;
+pub mod diagnostic;
+pub mod environment;
 pub mod error;
+mod invocation;
+pub mod module_info;
+pub mod process;
+#[cfg(feature = "runtime")]
+pub mod runtime;
+pub mod source_map;
+pub mod symbolication;
+pub mod toolchain;
 
+use batch::{JobserverClient, Parallelism};
+use cache::Cache;
+use cargo::{CargoConfiguration, Workspace};
+use compilation::Compilation;
 use configuration::Configuration;
-use error::{Error, VersionError};
+use environment::EnvironmentReport;
+use error::{Error, Failure, VersionError};
+use process::Cancellation;
+use toolchain::{Toolchain, ToolchainInfo};
 
 pub trait FileOps {
     /// Create temporary file
@@ -21,6 +44,16 @@ pub trait FileOps {
     /// When temporary file creation fails
     fn create_temp_exact(filename: &str) -> std::io::Result<(tempfile::TempDir, PathBuf, File)>;
 
+    /// Create file, including its missing parent directories
+    /// # Errors
+    /// When directory or file creation fails
+    fn create_file_all(path: &std::path::Path) -> std::io::Result<File> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        File::create(path)
+    }
+
     /// Writes content to path
     /// # Errors
     /// When writing fails
@@ -63,40 +96,231 @@ pub struct AbstractCompiler<FS: FileOps> {
 
 impl<FS: FileOps> AbstractCompiler<FS> {
     /// Compiles the current configuration into a WebAssembly module using
-    /// the [emscripten compiler](https://emscripten.org/).
+    /// the `rustc` of its toolchain, for its target.
     ///
     /// # Errors
+    /// - If the configuration is inconsistent
     /// - If using the host's file system fails.
     /// - If compilation fails
-    pub fn compile(configuration: &Configuration) -> Result<Vec<u8>, Error> {
-        let file_name = match &configuration.filename {
-            configuration::Filename::Unspecified => "rustc-to-wasm-source.rs",
-            configuration::Filename::Configured(filename) => filename.as_str(),
-        };
+    /// - If compilation exceeds the configured timeout
+    /// - If the module cannot be rendered as text when requested
+    /// - If the module lacks one of the required exports
+    /// - If no source map can be generated for the module when requested
+    pub fn compile(configuration: &Configuration) -> Result<Compilation, Error> {
+        Self::compile_cancellable(configuration, &Cancellation::new())
+    }
+
+    /// Compiles like [`AbstractCompiler::compile`], killing `rustc` and the
+    /// processes it spawned as soon as `cancellation` is cancelled.
+    ///
+    /// # Errors
+    /// - If the configuration is inconsistent
+    /// - If using the host's file system fails.
+    /// - If compilation fails
+    /// - If compilation exceeds the configured timeout or is cancelled
+    /// - If the module cannot be rendered as text when requested
+    /// - If the module lacks one of the required exports
+    /// - If no source map can be generated for the module when requested
+    pub fn compile_cancellable(
+        configuration: &Configuration,
+        cancellation: &Cancellation,
+    ) -> Result<Compilation, Error> {
+        Self::compile_with_jobserver(configuration, cancellation, None)
+    }
+
+    /// Compiles like [`AbstractCompiler::compile_cancellable`], with `rustc`
+    /// taking the tokens for its additional threads from `jobserver`
+    fn compile_with_jobserver(
+        configuration: &Configuration,
+        cancellation: &Cancellation,
+        jobserver: Option<&JobserverClient>,
+    ) -> Result<Compilation, Error> {
+        invocation::complete(invocation::compile(
+            &Blocking::<FS> {
+                cancellation,
+                jobserver,
+                _fs: core::marker::PhantomData,
+            },
+            configuration,
+        ))
+    }
+}
+
+/// Host of the blocking compiler, waiting for `rustc` on the current thread
+struct Blocking<'a, FS: FileOps> {
+    cancellation: &'a Cancellation,
+    jobserver: Option<&'a JobserverClient>,
+    _fs: core::marker::PhantomData<FS>,
+}
+
+impl<FS: FileOps> invocation::Host for Blocking<'_, FS> {
+    type File = File;
+
+    async fn create_temp_exact(
+        filename: &str,
+    ) -> std::io::Result<(tempfile::TempDir, PathBuf, File)> {
+        FS::create_temp_exact(filename)
+    }
+
+    async fn create_file_all(path: &std::path::Path) -> std::io::Result<File> {
+        FS::create_file_all(path)
+    }
+
+    async fn write_all(file: &mut File, data: &[u8]) -> std::io::Result<()> {
+        FS::write_all(file, data)
+    }
+
+    async fn read_file(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+        FS::read_file(path)
+    }
+
+    async fn run(
+        &self,
+        mut command: std::process::Command,
+        limits: &process::Limits,
+    ) -> Result<std::process::Output, Error> {
+        if let Some(jobserver) = self.jobserver {
+            jobserver.configure(&mut command);
+        }
+        process::run(command, limits, self.cancellation)
+    }
+
+    async fn finish(
+        configuration: &Configuration,
+        directories: Vec<tempfile::TempDir>,
+        wasm: Vec<u8>,
+        diagnostics: Vec<diagnostic::Diagnostic>,
+        artifacts: BTreeMap<configuration::Emit, Vec<u8>>,
+    ) -> Result<Compilation, Error> {
+        drop(directories);
+        Compilation::new(configuration, wasm, diagnostics, artifacts)
+    }
+}
+
+impl<FS: FileOps> AbstractCompiler<FS> {
+    /// Compiles the configuration like [`AbstractCompiler::compile`], unless
+    /// the cache already holds its compilation by the current `rustc`.
+    ///
+    /// # Errors
+    /// - If the version of `rustc` cannot be determined
+    /// - If compilation fails
+    /// - If storing the compilation in the cache fails
+    pub fn compile_cached(
+        configuration: &Configuration,
+        cache: &Cache,
+    ) -> Result<Compilation, Error> {
+        let key = cache.key(configuration).map_err(Error::Version)?;
+        if let Some(compilation) = cache.get::<FS>(&key, configuration) {
+            return Ok(compilation);
+        }
+
+        let compilation = Self::compile(configuration)?;
+        cache.insert::<FS>(key, &compilation).map_err(Error::IO)?;
+        Ok(compilation)
+    }
+
+    /// Compiles every configuration like [`AbstractCompiler::compile`],
+    /// running as many compilations at the same time as `parallelism`
+    /// allows. The results are in the order of the configurations.
+    #[must_use]
+    pub fn compile_many(
+        configurations: &[Configuration],
+        parallelism: &Parallelism,
+    ) -> Vec<Result<Compilation, Error>> {
+        batch::run(configurations, parallelism, |configuration, jobserver| {
+            Self::compile_with_jobserver(configuration, &Cancellation::new(), jobserver)
+        })
+    }
+
+    /// Builds a Cargo package into a WebAssembly module, yielding its
+    /// `cdylib` artifact.
+    ///
+    /// # Errors
+    /// - If the configuration is inconsistent
+    /// - If using the host's file system fails.
+    /// - If the build fails or produces no `cdylib`
+    /// - If the build exceeds the configured timeout
+    pub fn compile_cargo(configuration: &CargoConfiguration) -> Result<Compilation, Error> {
+        Self::compile_cargo_cancellable(configuration, &Cancellation::new())
+    }
 
-        let (source_parent_dir, input_path, mut input_source) =
-            FS::create_temp_exact(file_name).map_err(Error::IO)?;
+    /// Builds like [`AbstractCompiler::compile_cargo`], killing `cargo` and
+    /// the processes it spawned as soon as `cancellation` is cancelled.
+    ///
+    /// # Errors
+    /// - If the configuration is inconsistent
+    /// - If using the host's file system fails.
+    /// - If the build fails or produces no `cdylib`
+    /// - If the build exceeds the configured timeout or is cancelled
+    pub fn compile_cargo_cancellable(
+        configuration: &CargoConfiguration,
+        cancellation: &Cancellation,
+    ) -> Result<Compilation, Error> {
+        configuration.validate()?;
 
-        let (out_parent_dir, output_path, _output_wasm) =
-            FS::create_temp_exact("rustc-to-wasm-out.wasm").map_err(Error::IO)?;
+        // Materialize an in-memory workspace, keep the directory alive until done
+        let (workspace_dir, temp_workspace) = match &configuration.workspace {
+            Workspace::Directory(directory) => (directory.clone(), None),
+            Workspace::InMemory { manifest, files } => {
+                let (temp_dir, manifest_path, mut manifest_file) =
+                    FS::create_temp_exact("Cargo.toml").map_err(Error::IO)?;
+                FS::write_all(&mut manifest_file, manifest.as_bytes()).map_err(Error::IO)?;
+                for (path, contents) in files {
+                    let mut file =
+                        FS::create_file_all(&temp_dir.path().join(path)).map_err(Error::IO)?;
+                    FS::write_all(&mut file, contents).map_err(Error::IO)?;
+                }
+                let directory = manifest_path
+                    .parent()
+                    .map(PathBuf::from)
+                    .unwrap_or_default();
+                (directory, Some(temp_dir))
+            }
+        };
 
-        // Write into temp file
-        FS::write_all(&mut input_source, configuration.source().as_bytes()).map_err(Error::IO)?;
+        let command = configuration.as_command(&workspace_dir);
+        let output = process::run(command, &configuration.limits, cancellation)?;
 
-        let mut command = configuration.as_command(&input_path, &output_path);
-        let output = command.output().map_err(Error::IO)?;
+        let cargo::Messages {
+            diagnostics,
+            cdylibs,
+        } = cargo::parse(&output.stdout).map_err(Error::DiagnosticsUnreadable)?;
 
         if !output.status.success() {
-            return Err(Error::Unsuccesful(output));
+            // Failures outside of `rustc` (e.g. an invalid manifest) only show
+            // up in the human readable output of `cargo`
+            return Err(Error::Unsuccesful(Failure {
+                status: output.status,
+                diagnostics,
+                unstructured: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }));
         }
 
-        // Read from temp file
-        let output_content = FS::read_file(&output_path).map_err(Error::IO)?;
+        // Prefer the artifact of the selected package, the last one otherwise
+        let selected = configuration
+            .package
+            .as_ref()
+            .map(|spec| spec.split_once('@').map_or(spec.as_str(), |(name, _)| name));
+        let artifact = cdylibs
+            .iter()
+            .rev()
+            .find(|(package_id, _)| {
+                selected.is_none_or(|selected| selected == cargo::package_name(package_id))
+            })
+            .ok_or(Error::MissingArtifact)?;
 
-        drop(source_parent_dir);
-        drop(out_parent_dir);
+        let output_content = FS::read_file(&artifact.1).map_err(Error::IO)?;
 
-        Ok(output_content)
+        drop(temp_workspace);
+
+        Ok(Compilation {
+            wasm: output_content,
+            crate_type: configuration::CrateType::Cdylib,
+            diagnostics,
+            artifacts: BTreeMap::new(),
+            wat: None,
+            source_map: None,
+        })
     }
 }
 
@@ -109,20 +333,17 @@ impl Compiler {
     /// - If `rustc` is not installed on the host
     /// - If the version cannot be read from the command output
     pub fn version() -> Result<Version, VersionError> {
-        // Invoke command to request emcc version
-        let output = Command::new("rustc")
-            .arg("--version")
-            .output()
-            .map_err(VersionError::IO)?;
-
-        // If invocation failed, yield early
-        if !output.status.success() {
-            return Err(VersionError::InvocationNoSuccess(output));
-        }
+        Self::version_of(&Toolchain::default())
+    }
 
-        // Parse command output to `String`
-        let command_output =
-            String::try_from(output.stdout).map_err(VersionError::AttemptReadStdOut)?;
+    /// Yields the version of the `rustc` compiler of a toolchain as a semver
+    /// struct, that toolchain being the one configurations can select.
+    ///
+    /// # Errors
+    /// - If the `rustc` of the toolchain cannot be invoked
+    /// - If the version cannot be read from the command output
+    pub fn version_of(toolchain: &Toolchain) -> Result<Version, VersionError> {
+        let command_output = toolchain.version_output()?;
 
         // Parse command ourput to matching semver specification regex
         let Some(semver) = RustcSemVerRegex::new().captures(&command_output) else {
@@ -132,4 +353,32 @@ impl Compiler {
         // Parse matching regex specification to `Semver`
         Version::parse(semver.semver.content).map_err(VersionError::VersionParseFailed)
     }
+
+    /// Describes the `rustc` on the `PATH`, see [`Compiler::toolchain_info_of`].
+    ///
+    /// # Errors
+    /// - If `rustc` is not installed on the host
+    /// - If the description cannot be read from the command output
+    pub fn toolchain_info() -> Result<ToolchainInfo, VersionError> {
+        Self::toolchain_info_of(&Toolchain::default())
+    }
+
+    /// Describes a toolchain: its release, commit, channel, host, LLVM
+    /// version, sysroot and the wasm targets it has installed.
+    ///
+    /// # Errors
+    /// - If the `rustc` of the toolchain cannot be invoked
+    /// - If the description cannot be read from the command output
+    pub fn toolchain_info_of(toolchain: &Toolchain) -> Result<ToolchainInfo, VersionError> {
+        ToolchainInfo::query(toolchain)
+    }
+
+    /// Checks that the toolchain of the configuration can compile for its
+    /// target: `rustc` runs, the standard library of the target is installed
+    /// and the linkers are available. Meant to run before compiling, so
+    /// users get told what to install instead of a deep compilation error.
+    #[must_use]
+    pub fn check_environment(configuration: &Configuration) -> EnvironmentReport {
+        EnvironmentReport::check(configuration.toolchain(), *configuration.target())
+    }
 }
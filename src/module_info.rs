@@ -0,0 +1,386 @@
+use wasmparser::{Encoding, ExternalKind, KnownCustom, Name, Parser, Payload, TypeRef};
+
+use crate::error::ModuleInfoError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+    /// A reference type, e.g. `funcref` or `externref`
+    Ref(String),
+}
+
+impl From<wasmparser::ValType> for ValueType {
+    fn from(value: wasmparser::ValType) -> Self {
+        match value {
+            wasmparser::ValType::I32 => ValueType::I32,
+            wasmparser::ValType::I64 => ValueType::I64,
+            wasmparser::ValType::F32 => ValueType::F32,
+            wasmparser::ValType::F64 => ValueType::F64,
+            wasmparser::ValType::V128 => ValueType::V128,
+            wasmparser::ValType::Ref(reference) => ValueType::Ref(reference.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionType {
+    pub params: Vec<ValueType>,
+    pub results: Vec<ValueType>,
+}
+
+/// Table limits are expressed in elements
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TableType {
+    pub element: ValueType,
+    pub initial: u64,
+    pub maximum: Option<u64>,
+}
+
+/// Memory limits are expressed in pages
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MemoryType {
+    pub initial: u64,
+    pub maximum: Option<u64>,
+    pub shared: bool,
+    pub memory64: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlobalType {
+    pub value: ValueType,
+    pub mutable: bool,
+}
+
+/// The type of an imported or exported item
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExternType {
+    Function(FunctionType),
+    Table(TableType),
+    Memory(MemoryType),
+    Global(GlobalType),
+    /// An exception tag, typed by the values it carries
+    Tag(FunctionType),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Export {
+    pub name: String,
+    pub ty: ExternType,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub ty: ExternType,
+}
+
+/// Size in bytes of a section, custom sections are named after their name
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Section {
+    pub name: String,
+    pub size: usize,
+}
+
+/// What a WebAssembly module imports, defines and exports
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ModuleInfo {
+    pub exports: Vec<Export>,
+    pub imports: Vec<Import>,
+    /// Memories defined by the module, imported ones are among the imports
+    pub memories: Vec<MemoryType>,
+    /// Tables defined by the module, imported ones are among the imports
+    pub tables: Vec<TableType>,
+    /// Globals defined by the module, imported ones are among the imports
+    pub globals: Vec<GlobalType>,
+    /// The custom sections, e.g. `name`, `producers` or debug information
+    pub custom_sections: Vec<Section>,
+    /// Names of the functions as recorded in the name section, mangled
+    /// unless exported under that name
+    pub function_names: Vec<String>,
+    /// Every section in the order of the module
+    pub sections: Vec<Section>,
+    /// Size in bytes of the whole module
+    pub size: usize,
+}
+
+impl ModuleInfo {
+    /// Inspects a WebAssembly module
+    ///
+    /// # Errors
+    /// - When the bytes are not a WebAssembly module
+    /// - When the module refers to types it does not define
+    pub fn parse(wasm: &[u8]) -> Result<Self, ModuleInfoError> {
+        let mut info = ModuleInfo {
+            size: wasm.len(),
+            ..ModuleInfo::default()
+        };
+
+        let mut spaces = IndexSpaces::default();
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            let payload = payload.map_err(ModuleInfoError::Unreadable)?;
+
+            if let Some((id, range)) = payload.as_section() {
+                let name = match &payload {
+                    Payload::CustomSection(custom) => custom.name().to_string(),
+                    _ => section_name(id).to_string(),
+                };
+                let section = Section {
+                    name,
+                    size: range.len(),
+                };
+                if let Payload::CustomSection(_) = payload {
+                    info.custom_sections.push(section.clone());
+                }
+                info.sections.push(section);
+            }
+
+            match payload {
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    ..
+                } => return Err(ModuleInfoError::Component),
+                Payload::TypeSection(reader) => spaces.define_types(reader)?,
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.map_err(ModuleInfoError::Unreadable)?;
+                        info.imports.push(Import {
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                            ty: spaces.import(import.ty)?,
+                        });
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for index in reader {
+                        let index = index.map_err(ModuleInfoError::Unreadable)?;
+                        spaces.functions.push(index);
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table = table_type(table.map_err(ModuleInfoError::Unreadable)?.ty);
+                        spaces.tables.push(table.clone());
+                        info.tables.push(table);
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        let memory = memory_type(memory.map_err(ModuleInfoError::Unreadable)?);
+                        spaces.memories.push(memory.clone());
+                        info.memories.push(memory);
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        let global = global_type(global.map_err(ModuleInfoError::Unreadable)?.ty);
+                        spaces.globals.push(global.clone());
+                        info.globals.push(global);
+                    }
+                }
+                Payload::TagSection(reader) => {
+                    for tag in reader {
+                        let tag = tag.map_err(ModuleInfoError::Unreadable)?;
+                        spaces.tags.push(tag.func_type_idx);
+                    }
+                }
+                Payload::CustomSection(custom) => {
+                    if let KnownCustom::Name(reader) = custom.as_known() {
+                        info.function_names.extend(function_names(reader));
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export.map_err(ModuleInfoError::Unreadable)?;
+                        info.exports.push(Export {
+                            name: export.name.to_string(),
+                            ty: spaces.export(&export)?,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+
+    #[must_use]
+    pub fn export(&self, name: &str) -> Option<&Export> {
+        self.exports.iter().find(|export| export.name == name)
+    }
+
+    /// The signature of an exported function, e.g. to check that a
+    /// `#[no_mangle] pub extern "C" fn` made it into the module
+    #[must_use]
+    pub fn exported_function(&self, name: &str) -> Option<&FunctionType> {
+        match &self.export(name)?.ty {
+            ExternType::Function(function) => Some(function),
+            _ => None,
+        }
+    }
+}
+
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+/// The function names of a name section, which is informative, so malformed
+/// parts are skipped
+fn function_names(reader: wasmparser::NameSectionReader) -> Vec<String> {
+    let mut function_names = vec![];
+    for name in reader.into_iter().map_while(Result::ok) {
+        if let Name::Function(names) = name {
+            function_names.extend(
+                names
+                    .into_iter()
+                    .map_while(Result::ok)
+                    .map(|naming| naming.name.to_string()),
+            );
+        }
+    }
+    function_names
+}
+
+fn convert(types: &[wasmparser::ValType]) -> Vec<ValueType> {
+    types.iter().copied().map(ValueType::from).collect()
+}
+
+/// The index spaces of a module, imported items first, through which its
+/// items are resolved
+#[derive(Default)]
+struct IndexSpaces {
+    /// Only function types are of interest, other types are `None`
+    types: Vec<Option<FunctionType>>,
+    /// The type index of every function
+    functions: Vec<u32>,
+    tables: Vec<TableType>,
+    memories: Vec<MemoryType>,
+    globals: Vec<GlobalType>,
+    /// The type index of every tag
+    tags: Vec<u32>,
+}
+
+impl IndexSpaces {
+    fn function_type(&self, index: u32) -> Result<FunctionType, ModuleInfoError> {
+        self.types
+            .get(index as usize)
+            .cloned()
+            .flatten()
+            .ok_or(ModuleInfoError::UnknownFunctionType(index))
+    }
+
+    fn define_types(
+        &mut self,
+        reader: wasmparser::TypeSectionReader,
+    ) -> Result<(), ModuleInfoError> {
+        for rec_group in reader {
+            let rec_group = rec_group.map_err(ModuleInfoError::Unreadable)?;
+            for sub_type in rec_group.into_types() {
+                self.types.push(match sub_type.composite_type.inner {
+                    wasmparser::CompositeInnerType::Func(function) => Some(FunctionType {
+                        params: convert(function.params()),
+                        results: convert(function.results()),
+                    }),
+                    _ => None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds an imported item to its index space
+    fn import(&mut self, ty: TypeRef) -> Result<ExternType, ModuleInfoError> {
+        Ok(match ty {
+            TypeRef::Func(index) | TypeRef::FuncExact(index) => {
+                self.functions.push(index);
+                ExternType::Function(self.function_type(index)?)
+            }
+            TypeRef::Table(table) => {
+                self.tables.push(table_type(table));
+                ExternType::Table(table_type(table))
+            }
+            TypeRef::Memory(memory) => {
+                self.memories.push(memory_type(memory));
+                ExternType::Memory(memory_type(memory))
+            }
+            TypeRef::Global(global) => {
+                self.globals.push(global_type(global));
+                ExternType::Global(global_type(global))
+            }
+            TypeRef::Tag(tag) => {
+                self.tags.push(tag.func_type_idx);
+                ExternType::Tag(self.function_type(tag.func_type_idx)?)
+            }
+        })
+    }
+
+    /// Resolves the type of an exported item
+    fn export(&self, export: &wasmparser::Export) -> Result<ExternType, ModuleInfoError> {
+        let index = export.index as usize;
+        let missing = || ModuleInfoError::UnknownItem(export.name.to_string());
+        Ok(match export.kind {
+            ExternalKind::Func | ExternalKind::FuncExact => {
+                let type_index = *self.functions.get(index).ok_or_else(missing)?;
+                ExternType::Function(self.function_type(type_index)?)
+            }
+            ExternalKind::Table => {
+                ExternType::Table(self.tables.get(index).ok_or_else(missing)?.clone())
+            }
+            ExternalKind::Memory => {
+                ExternType::Memory(self.memories.get(index).ok_or_else(missing)?.clone())
+            }
+            ExternalKind::Global => {
+                ExternType::Global(self.globals.get(index).ok_or_else(missing)?.clone())
+            }
+            ExternalKind::Tag => {
+                let type_index = *self.tags.get(index).ok_or_else(missing)?;
+                ExternType::Tag(self.function_type(type_index)?)
+            }
+        })
+    }
+}
+
+fn table_type(table: wasmparser::TableType) -> TableType {
+    TableType {
+        element: ValueType::Ref(table.element_type.to_string()),
+        initial: table.initial,
+        maximum: table.maximum,
+    }
+}
+
+fn memory_type(memory: wasmparser::MemoryType) -> MemoryType {
+    MemoryType {
+        initial: memory.initial,
+        maximum: memory.maximum,
+        shared: memory.shared,
+        memory64: memory.memory64,
+    }
+}
+
+fn global_type(global: wasmparser::GlobalType) -> GlobalType {
+    GlobalType {
+        value: global.content_type.into(),
+        mutable: global.mutable,
+    }
+}
@@ -0,0 +1,365 @@
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+
+/// How often a running compilation is checked for completion, timeout and
+/// cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Limits imposed on the processes that compile.
+///
+/// Besides the timeout, these are resource limits which are only supported
+/// on Linux. They apply to `rustc` and are inherited by every process it
+/// spawns, e.g. the linker, each of which is limited on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Limits {
+    timeout: Option<Duration>,
+    memory: Option<u64>,
+    cpu_time: Option<Duration>,
+    file_size: Option<u64>,
+    processes: Option<u64>,
+}
+
+impl Limits {
+    /// No limits at all
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximal wall-clock time the compilation may take
+    #[must_use]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Maximal size in bytes of the virtual address space of a process
+    #[must_use]
+    pub fn memory(self, bytes: u64) -> Self {
+        Self {
+            memory: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Maximal CPU time of a process, rounded up to whole seconds
+    #[must_use]
+    pub fn cpu_time(self, cpu_time: Duration) -> Self {
+        Self {
+            cpu_time: Some(cpu_time),
+            ..self
+        }
+    }
+
+    /// Maximal size in bytes of a file written by a process
+    #[must_use]
+    pub fn file_size(self, bytes: u64) -> Self {
+        Self {
+            file_size: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Maximal number of processes and threads, note that Linux counts all
+    /// those of the user, and does not apply this limit to root.
+    #[must_use]
+    pub fn processes(self, count: u64) -> Self {
+        Self {
+            processes: Some(count),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn selected_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    #[must_use]
+    pub fn selected_memory(&self) -> Option<u64> {
+        self.memory
+    }
+
+    #[must_use]
+    pub fn selected_cpu_time(&self) -> Option<Duration> {
+        self.cpu_time
+    }
+
+    #[must_use]
+    pub fn selected_file_size(&self) -> Option<u64> {
+        self.file_size
+    }
+
+    #[must_use]
+    pub fn selected_processes(&self) -> Option<u64> {
+        self.processes
+    }
+
+    fn has_resource_limits(&self) -> bool {
+        self.memory.is_some()
+            || self.cpu_time.is_some()
+            || self.file_size.is_some()
+            || self.processes.is_some()
+    }
+
+    /// Applies the resource limits to the process `command` spawns
+    #[cfg(target_os = "linux")]
+    fn include_in(&self, command: &mut Command) {
+        if !self.has_resource_limits() {
+            return;
+        }
+
+        let cpu_seconds = self
+            .cpu_time
+            .map(|cpu_time| cpu_time.as_secs() + u64::from(cpu_time.subsec_nanos() > 0));
+        let Self {
+            memory,
+            file_size,
+            processes,
+            ..
+        } = *self;
+
+        let set = |resource, soft: u64, hard: u64| {
+            let limit = libc::rlimit {
+                rlim_cur: soft,
+                rlim_max: hard,
+            };
+            // SAFETY: `setrlimit` only reads the limit that is passed
+            match unsafe { libc::setrlimit(resource, &raw const limit) } {
+                0 => Ok(()),
+                _ => Err(std::io::Error::last_os_error()),
+            }
+        };
+
+        let apply = move || {
+            if let Some(bytes) = memory {
+                set(libc::RLIMIT_AS, bytes, bytes)?;
+            }
+            if let Some(seconds) = cpu_seconds {
+                // The soft limit signals SIGXCPU, the hard limit kills
+                set(libc::RLIMIT_CPU, seconds, seconds + 1)?;
+            }
+            if let Some(bytes) = file_size {
+                set(libc::RLIMIT_FSIZE, bytes, bytes)?;
+            }
+            if let Some(count) = processes {
+                set(libc::RLIMIT_NPROC, count, count)?;
+            }
+            Ok(())
+        };
+
+        // SAFETY: the closure does not allocate, `setrlimit` is async-signal-safe
+        unsafe {
+            std::os::unix::process::CommandExt::pre_exec(command, apply);
+        }
+    }
+
+    /// The limit a failed process ran into, if any. The processes mostly
+    /// fail in their own way, so this is recognized from the signal that
+    /// terminated `rustc` or from what it or its children reported outside
+    /// of the JSON diagnostics. Those quote the submitted source, which must
+    /// not be able to pass for a violation.
+    #[cfg(target_os = "linux")]
+    fn violation(&self, output: &Output) -> Option<Error> {
+        let signal = std::os::unix::process::ExitStatusExt::signal(&output.status);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reports: Vec<&str> = stderr
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('{'))
+            .collect();
+        let reported = |signatures: &[&str]| {
+            reports.iter().any(|report| {
+                signatures
+                    .iter()
+                    .any(|signature| report.contains(signature))
+            })
+        };
+
+        if let Some(cpu_time) = self.cpu_time
+            && (signal == Some(libc::SIGXCPU) || reported(&["SIGXCPU"]))
+        {
+            return Some(Error::CpuTimeLimitExceeded(cpu_time));
+        }
+        if let Some(bytes) = self.file_size
+            && (signal == Some(libc::SIGXFSZ)
+                || reported(&["SIGXFSZ", "File too large", "os error 27"]))
+        {
+            return Some(Error::FileSizeLimitExceeded(bytes));
+        }
+        if let Some(count) = self.processes
+            && reported(&["Resource temporarily unavailable", "os error 11"])
+        {
+            return Some(Error::ProcessLimitExceeded(count));
+        }
+        if let Some(bytes) = self.memory
+            && reported(&[
+                "memory allocation of",
+                "failed to map segment",
+                "Cannot allocate memory",
+                "os error 12",
+                "out of memory",
+            ])
+        {
+            return Some(Error::MemoryLimitExceeded(bytes));
+        }
+
+        None
+    }
+}
+
+/// Limits do not change what is compiled, so they are kept out of cache keys
+impl Hash for Limits {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Handle to cancel a running compilation, e.g. from another thread.
+///
+/// Clones share the same state, cancelling one cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kills the compilation that observes this handle, or prevents it from
+    /// starting if it did not yet
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs `command` to completion and collects its output, killing it together
+/// with the processes it spawned (e.g. the linker) once it exceeds the
+/// timeout or gets cancelled.
+pub(crate) fn run(
+    mut command: Command,
+    limits: &Limits,
+    cancellation: &Cancellation,
+) -> Result<Output, Error> {
+    let start = Instant::now();
+    if cancellation.is_cancelled() {
+        return Err(Error::Cancelled(start.elapsed()));
+    }
+
+    prepare(&mut command, limits)?;
+    let mut child = command.spawn().map_err(Error::IO)?;
+
+    // Drain the pipes while waiting, a full pipe would block the child
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(Error::IO)? {
+            break status;
+        }
+        let elapsed = start.elapsed();
+        if cancellation.is_cancelled() {
+            kill(&mut child);
+            return Err(Error::Cancelled(elapsed));
+        }
+        if limits.timeout.is_some_and(|timeout| elapsed >= timeout) {
+            kill(&mut child);
+            return Err(Error::TimedOut(elapsed));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    finish(
+        Output {
+            status,
+            stdout: collect(stdout)?,
+            stderr: collect(stderr)?,
+        },
+        limits,
+    )
+}
+
+/// Sets up `command` to be supervised: its output is captured, the resource
+/// limits are applied and it is spawned in a process group of its own, which
+/// lets the whole process tree be killed.
+#[cfg_attr(target_os = "linux", allow(clippy::unnecessary_wraps))]
+pub(crate) fn prepare(command: &mut Command, limits: &Limits) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    limits.include_in(command);
+    #[cfg(not(target_os = "linux"))]
+    if limits.has_resource_limits() {
+        return Err(Error::LimitsUnsupported);
+    }
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+
+    Ok(())
+}
+
+/// Reports a failure caused by running into a resource limit as such
+pub(crate) fn finish(output: Output, limits: &Limits) -> Result<Output, Error> {
+    #[cfg(target_os = "linux")]
+    if !output.status.success()
+        && let Some(violation) = limits.violation(&output)
+    {
+        return Err(violation);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = limits;
+
+    Ok(output)
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut content = vec![];
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut content)?;
+        }
+        Ok(content)
+    })
+}
+
+fn collect(pipe: JoinHandle<std::io::Result<Vec<u8>>>) -> Result<Vec<u8>, Error> {
+    pipe.join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        .map_err(Error::IO)
+}
+
+/// Kills the child and everything in its process group, then reaps it
+fn kill(child: &mut Child) {
+    kill_group(child.id());
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Kills every process in the group led by the process `id`, a no-op on
+/// platforms without process groups
+pub(crate) fn kill_group(id: u32) {
+    #[cfg(unix)]
+    if let Ok(group) = libc::pid_t::try_from(id) {
+        // SAFETY: signalling a process group has no memory safety requirements
+        unsafe {
+            libc::kill(-group, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = id;
+}
@@ -0,0 +1,437 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use wasmtime::{
+    Config, Engine, IntoFunc, Linker, Module, Store, Trap, WasmBacktrace, WasmParams, WasmResults,
+};
+use wasmtime_wasi::p1::{self, WasiP1Ctx};
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+use crate::configuration::is_nested;
+use crate::error::RuntimeError;
+
+/* re-export the runtime, e.g. to provide host imports through the linker */
+pub use wasmtime;
+
+/// Limits imposed on every call into a module, including the start function
+/// run on instantiation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ExecutionLimits {
+    fuel: Option<u64>,
+    epoch_deadline: Option<u64>,
+}
+
+impl ExecutionLimits {
+    /// No limits at all
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximal fuel a call may consume, roughly one unit per instruction
+    #[must_use]
+    pub fn fuel(self, fuel: u64) -> Self {
+        Self {
+            fuel: Some(fuel),
+            ..self
+        }
+    }
+
+    /// Number of epoch ticks after which a call is interrupted, the epoch
+    /// advances through [`CompiledModule::increment_epoch`], e.g. from a
+    /// timer thread
+    #[must_use]
+    pub fn epoch_deadline(self, ticks: u64) -> Self {
+        Self {
+            epoch_deadline: Some(ticks),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn selected_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    #[must_use]
+    pub fn selected_epoch_deadline(&self) -> Option<u64> {
+        self.epoch_deadline
+    }
+}
+
+/// A module compiled by wasmtime, ready to be instantiated
+pub struct CompiledModule {
+    engine: Engine,
+    module: Module,
+    linker: Linker<()>,
+    limits: ExecutionLimits,
+}
+
+impl CompiledModule {
+    /// Prepares the module for execution under the given limits
+    ///
+    /// # Errors
+    /// When the bytes are not a module wasmtime can compile
+    pub fn new(wasm: &[u8], limits: ExecutionLimits) -> Result<Self, RuntimeError> {
+        let mut config = Config::new();
+        config
+            .consume_fuel(limits.fuel.is_some())
+            .epoch_interruption(limits.epoch_deadline.is_some());
+        let engine = Engine::new(&config).map_err(RuntimeError::Module)?;
+        let module = Module::from_binary(&engine, wasm).map_err(RuntimeError::Module)?;
+        let linker = Linker::new(&engine);
+
+        Ok(Self {
+            engine,
+            module,
+            linker,
+            limits,
+        })
+    }
+
+    /// Provides a host function as the import `name` of `module`
+    ///
+    /// # Errors
+    /// When the import was already provided
+    pub fn func_wrap<Params, Results>(
+        &mut self,
+        module: &str,
+        name: &str,
+        func: impl IntoFunc<(), Params, Results>,
+    ) -> Result<&mut Self, RuntimeError> {
+        self.linker
+            .func_wrap(module, name, func)
+            .map_err(RuntimeError::Link)?;
+        Ok(self)
+    }
+
+    /// The linker that resolves the imports, for imports besides functions
+    pub fn linker_mut(&mut self) -> &mut Linker<()> {
+        &mut self.linker
+    }
+
+    #[must_use]
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Advances the epoch of every instance by one tick
+    pub fn increment_epoch(&self) {
+        self.engine.increment_epoch();
+    }
+
+    /// Instantiates the module, resolving its imports through the linker
+    ///
+    /// # Errors
+    /// - If an import is not provided
+    /// - If the start function traps or exceeds the limits
+    pub fn instantiate(&self) -> Result<Instance, RuntimeError> {
+        let mut store = Store::new(&self.engine, ());
+        replenish(&mut store, self.limits)?;
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .map_err(|error| classify(error, RuntimeError::Link))?;
+
+        Ok(Instance {
+            store,
+            inner: instance,
+            limits: self.limits,
+        })
+    }
+
+    /// Runs the module as a WASI (preview 1) command, i.e. its `fn main`.
+    /// Only WASI is imported, the host functions of the linker are not.
+    ///
+    /// # Errors
+    /// - If the preopened directories cannot be written
+    /// - If the module imports more than WASI
+    /// - If the module has no `_start`
+    /// - If WASI fails on the side of the host
+    pub fn run_wasi(&self, run: &WasiRun) -> Result<WasiOutput, RuntimeError> {
+        let limit = run.selected_output_limit();
+        // One byte beyond the limit tells exceeding it apart from reaching it
+        let stdout = MemoryOutputPipe::new(limit.saturating_add(1));
+        let stderr = MemoryOutputPipe::new(limit.saturating_add(1));
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .args(&run.args)
+            .envs(&run.envs)
+            .stdin(MemoryInputPipe::new(run.stdin.clone()))
+            .stdout(stdout.clone())
+            .stderr(stderr.clone());
+
+        // Each preopened directory lives in a temporary directory for the run
+        let mut directories = vec![];
+        for (guest_path, files) in &run.preopens {
+            let directory = tempfile::TempDir::new().map_err(RuntimeError::IO)?;
+            for (path, contents) in files {
+                if !is_nested(path) {
+                    return Err(RuntimeError::InvalidPreopenPath(path.clone()));
+                }
+                let path = directory.path().join(path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(RuntimeError::IO)?;
+                }
+                std::fs::write(path, contents).map_err(RuntimeError::IO)?;
+            }
+            builder
+                .preopened_dir(
+                    directory.path(),
+                    guest_path,
+                    DirPerms::all(),
+                    FilePerms::all(),
+                )
+                .map_err(RuntimeError::Link)?;
+            directories.push(directory);
+        }
+
+        let mut linker = Linker::new(&self.engine);
+        p1::add_to_linker_sync(&mut linker, |ctx: &mut WasiP1Ctx| ctx)
+            .map_err(RuntimeError::Link)?;
+        let mut store = Store::new(&self.engine, builder.build_p1());
+        replenish(&mut store, self.limits)?;
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .map_err(|error| classify(error, RuntimeError::Link))?;
+        let start = instance
+            .get_func(&mut store, "_start")
+            .ok_or_else(|| RuntimeError::UnknownExport("_start".into()))?
+            .typed::<(), ()>(&store)
+            .map_err(|error| RuntimeError::Signature("_start".into(), error))?;
+
+        replenish(&mut store, self.limits)?;
+        let outcome = start.call(&mut store, ());
+        drop(directories);
+
+        let mut stdout = stdout.contents().to_vec();
+        let mut stderr = stderr.contents().to_vec();
+        let status = if stdout.len() > limit || stderr.len() > limit {
+            // The program likely failed because its output was cut off
+            stdout.truncate(limit);
+            stderr.truncate(limit);
+            WasiStatus::OutputLimitExceeded
+        } else {
+            match outcome {
+                Ok(()) => WasiStatus::Exited(0),
+                Err(error) => match error.downcast_ref::<I32Exit>() {
+                    Some(exit) => WasiStatus::Exited(exit.0),
+                    None => match classify(error, RuntimeError::Host) {
+                        RuntimeError::Trap(trap, offsets) => WasiStatus::Trapped(trap, offsets),
+                        error => return Err(error),
+                    },
+                },
+            }
+        };
+
+        Ok(WasiOutput {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Calls an exported function of a fresh instance
+    ///
+    /// # Errors
+    /// - If instantiation fails
+    /// - If the call fails, see [`Instance::call`]
+    pub fn call<Params: WasmParams, Results: WasmResults>(
+        &self,
+        name: &str,
+        params: Params,
+    ) -> Result<Results, RuntimeError> {
+        self.instantiate()?.call(name, params)
+    }
+}
+
+/// An instance of a [`CompiledModule`] together with its store
+pub struct Instance {
+    store: Store<()>,
+    inner: wasmtime::Instance,
+    limits: ExecutionLimits,
+}
+
+impl Instance {
+    /// Calls the exported function `name`, e.g. `call::<i32, i32>("fac", 5)`
+    ///
+    /// # Errors
+    /// - If there is no such exported function
+    /// - If its signature does not match `Params` and `Results`
+    /// - If it traps, running out of fuel or past the epoch deadline included
+    /// - If a host function fails
+    pub fn call<Params: WasmParams, Results: WasmResults>(
+        &mut self,
+        name: &str,
+        params: Params,
+    ) -> Result<Results, RuntimeError> {
+        let function = self
+            .inner
+            .get_func(&mut self.store, name)
+            .ok_or_else(|| RuntimeError::UnknownExport(name.into()))?
+            .typed::<Params, Results>(&self.store)
+            .map_err(|error| RuntimeError::Signature(name.into(), error))?;
+
+        replenish(&mut self.store, self.limits)?;
+        function
+            .call(&mut self.store, params)
+            .map_err(|error| classify(error, RuntimeError::Host))
+    }
+
+    /// The fuel left after the last call, if fuel is limited
+    #[must_use]
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.limits.fuel.and(self.store.get_fuel().ok())
+    }
+
+    /// The underlying instance, e.g. to access its memory
+    #[must_use]
+    pub fn instance(&self) -> &wasmtime::Instance {
+        &self.inner
+    }
+
+    pub fn store_mut(&mut self) -> &mut Store<()> {
+        &mut self.store
+    }
+}
+
+/// Bytes a WASI command may write to stdout and to stderr each, unless
+/// configured otherwise, as both are kept in memory
+pub const DEFAULT_OUTPUT_LIMIT: usize = 1024 * 1024;
+
+/// Inputs of a WASI command run, see [`CompiledModule::run_wasi`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasiRun {
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    preopens: Vec<(String, BTreeMap<PathBuf, Vec<u8>>)>,
+    stdin: Vec<u8>,
+    output_limit: Option<usize>,
+}
+
+impl WasiRun {
+    /// No arguments, environment, directories nor input
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an argument, the first one being the program name
+    #[must_use]
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Sets an environment variable
+    #[must_use]
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Preopens a directory at `guest_path` holding `files`, keyed by their
+    /// path relative to the directory, e.g. `input.txt` or `data/input.txt`
+    #[must_use]
+    pub fn preopen(mut self, guest_path: &str, files: BTreeMap<PathBuf, Vec<u8>>) -> Self {
+        self.preopens.push((guest_path.into(), files));
+        self
+    }
+
+    /// The bytes the program reads from its standard input
+    #[must_use]
+    pub fn stdin(self, stdin: Vec<u8>) -> Self {
+        Self { stdin, ..self }
+    }
+
+    /// Maximal number of bytes the program may write to stdout and to stderr,
+    /// defaults to [`DEFAULT_OUTPUT_LIMIT`]
+    #[must_use]
+    pub fn output_limit(self, bytes: usize) -> Self {
+        Self {
+            output_limit: Some(bytes),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    #[must_use]
+    pub fn envs(&self) -> &[(String, String)] {
+        &self.envs
+    }
+
+    #[must_use]
+    pub fn preopens(&self) -> &[(String, BTreeMap<PathBuf, Vec<u8>>)] {
+        &self.preopens
+    }
+
+    #[must_use]
+    pub fn selected_stdin(&self) -> &[u8] {
+        &self.stdin
+    }
+
+    /// The configured output limit, or [`DEFAULT_OUTPUT_LIMIT`] otherwise
+    #[must_use]
+    pub fn selected_output_limit(&self) -> usize {
+        self.output_limit.unwrap_or(DEFAULT_OUTPUT_LIMIT)
+    }
+}
+
+/// How a WASI command run ended
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WasiStatus {
+    /// Returned from `main` (`0`) or called `exit`
+    Exited(i32),
+    /// Trapped, e.g. on a panic or when running out of fuel, with the module
+    /// offsets of its backtrace, innermost first
+    Trapped(Trap, Vec<usize>),
+    /// Wrote more than the output limit, the output is cut off at the limit
+    OutputLimitExceeded,
+}
+
+/// Outcome of a WASI command run, with the output captured so far
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasiOutput {
+    pub status: WasiStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Resets the limits of the store before the next call
+fn replenish<T>(store: &mut Store<T>, limits: ExecutionLimits) -> Result<(), RuntimeError> {
+    if let Some(fuel) = limits.fuel {
+        store.set_fuel(fuel).map_err(RuntimeError::Module)?;
+    }
+    if let Some(ticks) = limits.epoch_deadline {
+        store.set_epoch_deadline(ticks);
+    }
+    Ok(())
+}
+
+/// Reports traps as such, along with where they happened, other failures as
+/// `otherwise`
+fn classify(
+    error: wasmtime::Error,
+    otherwise: fn(wasmtime::Error) -> RuntimeError,
+) -> RuntimeError {
+    let Some(trap) = error.downcast_ref::<Trap>() else {
+        return otherwise(error);
+    };
+    let offsets = error
+        .downcast_ref::<WasmBacktrace>()
+        .map(|backtrace| {
+            backtrace
+                .frames()
+                .iter()
+                .filter_map(wasmtime::FrameInfo::module_offset)
+                .collect()
+        })
+        .unwrap_or_default();
+    RuntimeError::Trap(*trap, offsets)
+}
@@ -0,0 +1,275 @@
+use std::path::Path;
+
+use base64::Engine as _;
+use wasmparser::{BinaryReaderError, Chunk, Parser, Payload};
+
+use crate::configuration::{Configuration, SourceMap};
+use crate::error::{Error, SymbolicationError};
+use crate::symbolication::Symbolizer;
+
+/// Name of the custom section through which browsers find the source map
+const SECTION_NAME: &str = "sourceMappingURL";
+
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A source position: the index of the source, its 0-based line and column
+type Position = (usize, usize, usize);
+
+/// Generates the source map of a module `configuration` compiled into, from
+/// the DWARF it carries. The generated columns are byte offsets in the module
+/// and the sources are the files of the crate, contents included. Code from
+/// elsewhere, e.g. the standard library, is left unmapped.
+///
+/// # Errors
+/// - When the bytes are not a WebAssembly module
+/// - When the module carries no (readable) debug information
+pub fn generate(wasm: &[u8], configuration: &Configuration) -> Result<String, SymbolicationError> {
+    let line_ranges = Symbolizer::new(wasm)?.line_ranges()?;
+
+    let mut crate_files = vec![(
+        Path::new(configuration.file_name()),
+        configuration.source().to_string(),
+    )];
+    for (path, contents) in configuration.modules() {
+        crate_files.push((path, String::from_utf8_lossy(contents).into_owned()));
+    }
+
+    // Sources are listed in the order they are first mapped to
+    let mut sources: Vec<usize> = vec![];
+    let mut segments: Vec<(usize, Option<Position>)> = vec![];
+    let mut mapped_until = None;
+    for line_range in line_ranges {
+        if let Some(end) = mapped_until.filter(|end| *end < line_range.code.start) {
+            segments.push((end, None));
+        }
+
+        let crate_file = line_range.file.as_ref().and_then(|file| {
+            crate_files
+                .iter()
+                .position(|(path, _)| *path == file.as_path())
+        });
+        let position = match (crate_file, line_range.line) {
+            (Some(crate_file), Some(line)) if line > 0 => {
+                let source = sources
+                    .iter()
+                    .position(|source| *source == crate_file)
+                    .unwrap_or_else(|| {
+                        sources.push(crate_file);
+                        sources.len() - 1
+                    });
+                let column = line_range.column.unwrap_or(1).saturating_sub(1);
+                Some((source, line as usize - 1, column as usize))
+            }
+            _ => None,
+        };
+        segments.push((line_range.code.start, position));
+        mapped_until = Some(line_range.code.end);
+    }
+    if let Some(end) = mapped_until {
+        segments.push((end, None));
+    }
+
+    // Drop segments that start at the same offset or repeat the position before them
+    segments.dedup_by(|next, previous| next.0 == previous.0 || next.1 == previous.1);
+    while segments
+        .first()
+        .is_some_and(|(_, position)| position.is_none())
+    {
+        segments.remove(0);
+    }
+
+    // The revision 3 format browsers read
+    let sources_content: Vec<&str> = sources
+        .iter()
+        .map(|source| crate_files[*source].1.as_str())
+        .collect();
+    let sources: Vec<String> = sources
+        .iter()
+        .map(|source| crate_files[*source].0.to_string_lossy().into_owned())
+        .collect();
+    Ok(serde_json::json!({
+        "version": 3,
+        "sources": sources,
+        "sourcesContent": sources_content,
+        "names": [],
+        "mappings": mappings(&segments),
+    })
+    .to_string())
+}
+
+/// Generates the source map `configuration` asks for, referring to it from the
+/// `sourceMappingURL` section of `wasm` unless it is kept separate
+///
+/// # Errors
+/// When the module carries no (readable) debug information
+pub(crate) fn attach(
+    configuration: &Configuration,
+    wasm: &mut Vec<u8>,
+) -> Result<Option<String>, Error> {
+    match configuration.source_map() {
+        SourceMap::Disabled => return Ok(None),
+        SourceMap::Separate => {}
+        SourceMap::Linked(_) | SourceMap::Embedded => {
+            // Modules reloaded from a cache, or linked with a section of their
+            // own, already refer to a source map. The section is removed
+            // before mapping, as it may precede the code and shift its offsets
+            *wasm = without_custom_section(wasm, SECTION_NAME).map_err(|error| {
+                Error::SourceMapUnavailable(SymbolicationError::Unreadable(error))
+            })?;
+        }
+    }
+
+    let source_map = generate(wasm, configuration).map_err(Error::SourceMapUnavailable)?;
+
+    let url = match configuration.source_map() {
+        SourceMap::Disabled | SourceMap::Separate => None,
+        SourceMap::Linked(url) => Some(url.clone()),
+        SourceMap::Embedded => Some(format!(
+            "data:application/json;charset=utf-8;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&source_map)
+        )),
+    };
+
+    if let Some(url) = url {
+        wasm.extend(custom_section(SECTION_NAME, &name(&url)));
+    }
+
+    Ok(Some(source_map))
+}
+
+/// Encodes the segments of the single generated line, each relative to the one
+/// before it
+fn mappings(segments: &[(usize, Option<Position>)]) -> String {
+    let mut mappings = String::new();
+    let mut previous_offset = 0;
+    let mut previous_position = (0, 0, 0);
+    for (offset, position) in segments {
+        if !mappings.is_empty() {
+            mappings.push(',');
+        }
+        vlq(&mut mappings, *offset, previous_offset);
+        previous_offset = *offset;
+
+        if let Some((source, line, column)) = *position {
+            let (previous_source, previous_line, previous_column) = previous_position;
+            vlq(&mut mappings, source, previous_source);
+            vlq(&mut mappings, line, previous_line);
+            vlq(&mut mappings, column, previous_column);
+            previous_position = (source, line, column);
+        }
+    }
+    mappings
+}
+
+/// Appends `value - previous` as a base64 variable-length quantity, its sign
+/// in the lowest bit
+fn vlq(mappings: &mut String, value: usize, previous: usize) {
+    let mut rest = (value.abs_diff(previous) << 1) | usize::from(value < previous);
+    loop {
+        let mut digit = rest & 0b1_1111;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0b10_0000;
+        }
+        mappings.push(char::from(BASE64_DIGITS[digit]));
+        if rest == 0 {
+            return;
+        }
+    }
+}
+
+/// The module without the custom sections named `section_name`
+fn without_custom_section(wasm: &[u8], section_name: &str) -> Result<Vec<u8>, BinaryReaderError> {
+    let mut parser = Parser::new(0);
+    let mut kept = Vec::with_capacity(wasm.len());
+    let mut offset = 0;
+    loop {
+        let Chunk::Parsed { consumed, payload } = parser.parse(&wasm[offset..], true)? else {
+            unreachable!("the whole module is at hand");
+        };
+        match payload {
+            Payload::CustomSection(custom) if custom.name() == section_name => {}
+            Payload::End(_) => return Ok(kept),
+            _ => kept.extend(&wasm[offset..offset + consumed]),
+        }
+        offset += consumed;
+    }
+}
+
+/// A string as encoded in a module, prefixed with its length
+fn name(string: &str) -> Vec<u8> {
+    let mut name = leb128(string.len());
+    name.extend(string.as_bytes());
+    name
+}
+
+fn custom_section(section_name: &str, payload: &[u8]) -> Vec<u8> {
+    let mut contents = name(section_name);
+    contents.extend(payload);
+
+    let mut section = vec![0];
+    section.extend(leb128(contents.len()));
+    section.extend(contents);
+    section
+}
+
+fn leb128(mut value: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: usize, previous: usize) -> String {
+        let mut mappings = String::new();
+        vlq(&mut mappings, value, previous);
+        mappings
+    }
+
+    #[test]
+    fn vlq_encodes_known_values() {
+        assert_eq!(encoded(0, 0), "A");
+        assert_eq!(encoded(1, 0), "C");
+        assert_eq!(encoded(0, 1), "D");
+        assert_eq!(encoded(15, 0), "e");
+        assert_eq!(encoded(16, 0), "gB");
+        assert_eq!(encoded(0, 17), "jB");
+        assert_eq!(encoded(1000, 0), "w+B");
+        assert_eq!(encoded(0, 1000), "x+B");
+        assert_eq!(encoded(123_456_789, 0), "qxmvrH");
+        assert_eq!(encoded(1017, 17), "w+B");
+    }
+
+    #[test]
+    fn mappings_are_relative_to_the_previous_segment() {
+        let segments = [(10, Some((0, 2, 4))), (12, None), (20, Some((0, 1, 4)))];
+        assert_eq!(mappings(&segments), "UAEI,E,QADA");
+    }
+
+    #[test]
+    fn source_mapping_urls_are_replaced() -> Result<(), BinaryReaderError> {
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.extend(custom_section(SECTION_NAME, &name("old.map")));
+        module.extend(custom_section("name", b""));
+
+        let mut stripped = without_custom_section(&module, SECTION_NAME)?;
+        let mut expected = b"\0asm\x01\0\0\0".to_vec();
+        expected.extend(custom_section("name", b""));
+        assert_eq!(stripped, expected);
+
+        stripped.extend(custom_section(SECTION_NAME, &name("new.map")));
+        assert_eq!(without_custom_section(&stripped, SECTION_NAME)?, expected);
+        Ok(())
+    }
+}
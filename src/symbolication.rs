@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use gimli::{EndianArcSlice, LittleEndian, Reader as _, SectionId};
+use wasmparser::{Parser, Payload};
+
+use crate::error::SymbolicationError;
+
+type Reader = EndianArcSlice<LittleEndian>;
+
+/// A frame of a source-level stack trace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The demangled name of the function, e.g. `fac::helper`
+    pub function: Option<String>,
+    /// The file, relative to the directory it was compiled in. For the files
+    /// of the compiled crate this is the configured filename or module path.
+    pub file: Option<PathBuf>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Whether the function was inlined into the function of the next frame
+    pub inlined: bool,
+}
+
+/// A contiguous range of code, in module offsets, attributed to one source line
+pub(crate) struct LineRange {
+    pub(crate) code: Range<usize>,
+    pub(crate) file: Option<PathBuf>,
+    pub(crate) line: Option<u32>,
+    pub(crate) column: Option<u32>,
+}
+
+/// Maps code offsets of a module back to source locations, through the DWARF
+/// debug information the module carries when compiled with
+/// [`crate::configuration::Debugging::Enabled`]
+pub struct Symbolizer {
+    context: addr2line::Context<Reader>,
+    /// Range of the code section contents, DWARF addresses are relative to it
+    code: Range<usize>,
+    /// Directories the compilation units were compiled in, longest first
+    comp_dirs: Vec<PathBuf>,
+}
+
+impl Symbolizer {
+    /// Reads the debug information of a module
+    ///
+    /// # Errors
+    /// - When the bytes are not a WebAssembly module
+    /// - When the module carries no (readable) debug information
+    pub fn new(wasm: &[u8]) -> Result<Self, SymbolicationError> {
+        let mut sections = HashMap::new();
+        let mut code = None;
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.map_err(SymbolicationError::Unreadable)? {
+                Payload::CodeSectionStart { range, .. } => code = Some(range),
+                Payload::CustomSection(custom) if custom.name().starts_with(".debug_") => {
+                    sections.insert(custom.name(), custom.data());
+                }
+                _ => {}
+            }
+        }
+
+        let Some(code) = code.filter(|_| sections.contains_key(".debug_info")) else {
+            return Err(SymbolicationError::NoDebugInfo);
+        };
+
+        let dwarf = gimli::Dwarf::load(|id: SectionId| -> Result<Reader, gimli::Error> {
+            let data = sections.get(id.name()).copied().unwrap_or_default();
+            Ok(EndianArcSlice::new(Arc::from(data), LittleEndian))
+        })
+        .map_err(SymbolicationError::Dwarf)?;
+        let comp_dirs = comp_dirs(&dwarf).map_err(SymbolicationError::Dwarf)?;
+        let context = addr2line::Context::from_dwarf(dwarf).map_err(SymbolicationError::Dwarf)?;
+
+        Ok(Self {
+            context,
+            code,
+            comp_dirs,
+        })
+    }
+
+    /// The source frames at an offset in the module, e.g. from wasmtime's
+    /// `FrameInfo::module_offset`. Inlined functions come first, followed by
+    /// the functions they were inlined into.
+    ///
+    /// # Errors
+    /// - When the offset lies outside the code of the module
+    /// - When the debug information cannot be read
+    pub fn frames(&self, module_offset: usize) -> Result<Vec<Frame>, SymbolicationError> {
+        if !self.code.contains(&module_offset) {
+            return Err(SymbolicationError::OutsideCode(module_offset));
+        }
+
+        let probe = (module_offset - self.code.start) as u64;
+        let mut found = self
+            .context
+            .find_frames(probe)
+            .skip_all_loads()
+            .map_err(SymbolicationError::Dwarf)?;
+
+        let mut frames = vec![];
+        while let Some(frame) = found.next().map_err(SymbolicationError::Dwarf)? {
+            let function = frame
+                .function
+                .as_ref()
+                .map(|function| function.demangle().map(Cow::into_owned))
+                .transpose()
+                .map_err(SymbolicationError::Dwarf)?;
+            let location = frame.location.as_ref();
+            frames.push(Frame {
+                function,
+                file: location
+                    .and_then(|location| location.file)
+                    .map(|file| self.relative(Path::new(file))),
+                line: location.and_then(|location| location.line),
+                column: location.and_then(|location| location.column),
+                inlined: true,
+            });
+        }
+        if let Some(outermost) = frames.last_mut() {
+            outermost.inlined = false;
+        }
+
+        Ok(frames)
+    }
+
+    /// The source frames of a whole backtrace, its offsets innermost first
+    ///
+    /// # Errors
+    /// When one of the offsets cannot be symbolized, see [`Self::frames`]
+    pub fn backtrace(&self, module_offsets: &[usize]) -> Result<Vec<Frame>, SymbolicationError> {
+        let mut frames = vec![];
+        for offset in module_offsets {
+            frames.extend(self.frames(*offset)?);
+        }
+        Ok(frames)
+    }
+
+    /// The line table of the whole module, ordered by code offset
+    pub(crate) fn line_ranges(&self) -> Result<Vec<LineRange>, SymbolicationError> {
+        let code_size = (self.code.end - self.code.start) as u64;
+        let rows = self
+            .context
+            .find_location_range(0, code_size)
+            .map_err(SymbolicationError::Dwarf)?;
+
+        let mut line_ranges: Vec<LineRange> = rows
+            .filter_map(|(probe, length, location)| {
+                let start = self.code.start + usize::try_from(probe).ok()?;
+                let end = start + usize::try_from(length).ok()?;
+                Some(LineRange {
+                    code: start..end,
+                    file: location.file.map(|file| self.relative(Path::new(file))),
+                    line: location.line,
+                    column: location.column,
+                })
+            })
+            .collect();
+        line_ranges.sort_by_key(|line_range| line_range.code.start);
+        Ok(line_ranges)
+    }
+
+    fn relative(&self, file: &Path) -> PathBuf {
+        self.comp_dirs
+            .iter()
+            .find_map(|comp_dir| file.strip_prefix(comp_dir).ok())
+            .unwrap_or(file)
+            .to_path_buf()
+    }
+}
+
+fn comp_dirs(dwarf: &gimli::Dwarf<Reader>) -> Result<Vec<PathBuf>, gimli::Error> {
+    let mut comp_dirs = vec![];
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        if let Some(comp_dir) = unit.comp_dir {
+            comp_dirs.push(PathBuf::from(comp_dir.to_string_lossy()?.into_owned()));
+        }
+    }
+
+    // Nested directories take precedence over the directories they are in
+    comp_dirs.sort_by_key(|comp_dir| std::cmp::Reverse(comp_dir.as_os_str().len()));
+    comp_dirs.dedup();
+    Ok(comp_dirs)
+}
@@ -0,0 +1,248 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+use semver::Version;
+
+use crate::configuration::Target;
+use crate::error::VersionError;
+
+/// How the `rustc` binary is located
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Rustc {
+    /// The `rustc` found on the `PATH`
+    #[default]
+    FromPath,
+    /// An explicit `rustc` binary
+    Binary(PathBuf),
+    /// A toolchain installed through rustup, e.g. `nightly-2026-09-01`,
+    /// selected by invoking the rustup proxy as `rustc +<toolchain>`
+    Rustup(String),
+}
+
+/// The compiler that compiles, and reports its version, together with the
+/// sysroot its standard library is taken from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Toolchain {
+    rustc: Rustc,
+    sysroot: Option<PathBuf>,
+}
+
+impl Toolchain {
+    /// The `rustc` found on the `PATH` with its own sysroot
+    #[must_use]
+    pub fn from_path() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn binary(rustc: PathBuf) -> Self {
+        Self {
+            rustc: Rustc::Binary(rustc),
+            sysroot: None,
+        }
+    }
+
+    #[must_use]
+    pub fn rustup(toolchain: &str) -> Self {
+        Self {
+            rustc: Rustc::Rustup(toolchain.into()),
+            sysroot: None,
+        }
+    }
+
+    /// Overrides the sysroot passed to `rustc` through `--sysroot`
+    #[must_use]
+    pub fn sysroot(self, sysroot: PathBuf) -> Self {
+        Self {
+            sysroot: Some(sysroot),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn selected_rustc(&self) -> &Rustc {
+        &self.rustc
+    }
+
+    #[must_use]
+    pub fn selected_sysroot(&self) -> Option<&PathBuf> {
+        self.sysroot.as_ref()
+    }
+
+    /// A `rustc` invocation for this toolchain
+    pub(crate) fn rustc(&self) -> Command {
+        let mut command = match &self.rustc {
+            Rustc::FromPath => Command::new("rustc"),
+            Rustc::Binary(rustc) => Command::new(rustc),
+            Rustc::Rustup(toolchain) => {
+                let mut command = Command::new("rustc");
+                command.arg(format!("+{toolchain}"));
+                command
+            }
+        };
+        if let Some(sysroot) = &self.sysroot {
+            command.arg("--sysroot").arg(sysroot);
+        }
+        command
+    }
+
+    /// A `cargo` invocation whose builds for `target` use this toolchain.
+    ///
+    /// The sysroot is added to the flags the user configured: to the ones
+    /// in `CARGO_ENCODED_RUSTFLAGS` or `RUSTFLAGS` when inherited, otherwise
+    /// to the `target.<triple>.rustflags` of the Cargo configuration.
+    pub(crate) fn cargo(&self, target: Target) -> Command {
+        let mut command = Command::new("cargo");
+        match &self.rustc {
+            Rustc::FromPath => {}
+            Rustc::Binary(rustc) => {
+                command.env("RUSTC", rustc);
+            }
+            Rustc::Rustup(toolchain) => {
+                command.arg(format!("+{toolchain}"));
+            }
+        }
+        if let Some(sysroot) = &self.sysroot {
+            let mut flag = OsString::from("--sysroot=");
+            flag.push(sysroot);
+            match inherited_rustflags() {
+                Some(mut rustflags) => {
+                    if !rustflags.is_empty() {
+                        rustflags.push("\x1f");
+                    }
+                    rustflags.push(flag);
+                    command.env("CARGO_ENCODED_RUSTFLAGS", rustflags);
+                }
+                None => match flag.to_str() {
+                    // Joined with the flags of the configuration files
+                    Some(flag) => {
+                        let flag = serde_json::Value::from(flag);
+                        command
+                            .arg("--config")
+                            .arg(format!("target.{}.rustflags=[{flag}]", target.triple()));
+                    }
+                    None => {
+                        command.env("CARGO_ENCODED_RUSTFLAGS", flag);
+                    }
+                },
+            }
+        }
+        command
+    }
+
+    /// Yields the output of `rustc --version`, which identifies the compiler
+    /// down to its commit.
+    pub(crate) fn version_output(&self) -> Result<String, VersionError> {
+        self.query(&["--version"])
+    }
+
+    /// Yields the standard output of a `rustc` invocation of this toolchain
+    pub(crate) fn query(&self, args: &[&str]) -> Result<String, VersionError> {
+        // Invoke command to request the information
+        let output = self.rustc().args(args).output().map_err(VersionError::IO)?;
+
+        // If invocation failed, yield early
+        if !output.status.success() {
+            return Err(VersionError::InvocationNoSuccess(output));
+        }
+
+        // Parse command output to `String`
+        String::try_from(output.stdout).map_err(VersionError::AttemptReadStdOut)
+    }
+}
+
+/// The flags for `rustc` that this process hands down to `cargo` through its
+/// environment, in the encoding of `CARGO_ENCODED_RUSTFLAGS`
+fn inherited_rustflags() -> Option<OsString> {
+    if let Some(encoded) = std::env::var_os("CARGO_ENCODED_RUSTFLAGS") {
+        return Some(encoded);
+    }
+    // Cargo splits `RUSTFLAGS` at whitespace
+    let rustflags = std::env::var("RUSTFLAGS").ok()?;
+    Some(
+        rustflags
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("\x1f")
+            .into(),
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+    /// A locally built compiler
+    Dev,
+}
+
+/// Description of a toolchain, gathered from `rustc -vV` and `rustc --print`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolchainInfo {
+    pub release: Version,
+    pub channel: Channel,
+    pub commit_hash: Option<String>,
+    pub commit_date: Option<String>,
+    pub host: String,
+    pub llvm_version: Option<String>,
+    pub sysroot: PathBuf,
+    /// Triples of the wasm targets whose standard library is installed
+    pub wasm_targets: Vec<String>,
+}
+
+impl ToolchainInfo {
+    /// Whether the standard library for `target` is installed
+    #[must_use]
+    pub fn has_target(&self, target: Target) -> bool {
+        self.wasm_targets
+            .iter()
+            .any(|installed| installed == target.triple())
+    }
+
+    pub(crate) fn query(toolchain: &Toolchain) -> Result<Self, VersionError> {
+        let verbose_version = toolchain.query(&["-vV"])?;
+        let field = |name: &'static str| {
+            verbose_version.lines().find_map(|line| {
+                let (key, value) = line.split_once(": ")?;
+                (key == name).then(|| value.trim().to_string())
+            })
+        };
+        // Builds without git information report `unknown`
+        let known = |name| field(name).filter(|value| value != "unknown");
+
+        let release = field("release").ok_or(VersionError::MissingField("release"))?;
+        let release = Version::parse(&release).map_err(VersionError::VersionParseFailed)?;
+        let channel = match release.pre.as_str() {
+            "" => Channel::Stable,
+            pre if pre.starts_with("beta") => Channel::Beta,
+            pre if pre.starts_with("nightly") => Channel::Nightly,
+            _ => Channel::Dev,
+        };
+
+        let sysroot = PathBuf::from(toolchain.query(&["--print", "sysroot"])?.trim());
+
+        // A target is installed when its standard library is in the sysroot
+        let mut wasm_targets = std::fs::read_dir(sysroot.join("lib").join("rustlib"))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().join("lib").is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|triple| triple.starts_with("wasm"))
+            .collect::<Vec<_>>();
+        wasm_targets.sort();
+
+        Ok(Self {
+            release,
+            channel,
+            commit_hash: known("commit-hash"),
+            commit_date: known("commit-date"),
+            host: field("host").ok_or(VersionError::MissingField("host"))?,
+            llvm_version: field("LLVM version"),
+            sysroot,
+            wasm_targets,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::asynchronous::AsyncCompiler;
+use rustc_to_wasm_compiler::error::Error;
+#[cfg(unix)]
+use rustc_to_wasm_compiler::process::Limits;
+
+use crate::common::fac_config;
+#[cfg(unix)]
+use crate::fakes;
+
+#[tokio::test]
+async fn async_compilation_matches_blocking() -> anyhow::Result<()> {
+    let config = fac_config().build();
+
+    let asynchronous = AsyncCompiler::compile(&config).await?;
+    let blocking = Compiler::compile(&config)?;
+
+    assert_eq!(asynchronous.wasm(), blocking.wasm());
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_compile_errors_are_reported() {
+    let config = fac_config().source("fn broken(".into()).build();
+
+    assert!(matches!(
+        AsyncCompiler::compile(&config).await,
+        Err(Error::Unsuccesful(_))
+    ));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn async_compilation_times_out() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let limits = Limits::new().timeout(Duration::from_millis(200));
+    let config = fakes::fac_compiled_by(fakes::hanging_rustc(directory.path())?)
+        .limits(limits)
+        .build();
+
+    let result = AsyncCompiler::compile(&config).await;
+    assert!(matches!(result, Err(Error::TimedOut(_))), "{result:?}");
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn dropping_the_future_kills_the_process_tree() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let config = fakes::fac_compiled_by(fakes::hanging_rustc(directory.path())?).build();
+
+    let compilation = AsyncCompiler::compile(&config);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), compilation)
+            .await
+            .is_err()
+    );
+
+    let pid = std::fs::read_to_string(directory.path().join("pid"))?;
+    let pid = pid.trim();
+    for _ in 0..100 {
+        if !fakes::is_running(pid) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("process {pid} survived dropping the compilation");
+}
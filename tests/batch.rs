@@ -0,0 +1,137 @@
+use std::num::NonZeroUsize;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::batch::{JobserverClient, Parallelism};
+use rustc_to_wasm_compiler::configuration::Profile;
+use rustc_to_wasm_compiler::error::Error;
+
+use crate::common::fac_config;
+
+const PROFILE_OPTS: [Profile; 4] = [Profile::O0, Profile::O1, Profile::O2, Profile::O3];
+
+#[test]
+fn batch_results_are_in_input_order() {
+    let configurations = PROFILE_OPTS
+        .into_iter()
+        .flat_map(|profile| {
+            let fac = fac_config().profile(profile);
+            [fac.clone().build(), fac.source("fn broken(".into()).build()]
+        })
+        .collect::<Vec<_>>();
+
+    let results = Compiler::compile_many(&configurations, &Parallelism::new());
+
+    assert_eq!(results.len(), configurations.len());
+    for (index, result) in results.iter().enumerate() {
+        if index % 2 == 0 {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(matches!(result, Err(Error::Unsuccesful(_))));
+        }
+    }
+}
+
+#[test]
+fn empty_batch_yields_no_results() {
+    assert!(Compiler::compile_many(&[], &Parallelism::new()).is_empty());
+}
+
+#[test]
+fn default_jobs_match_available_parallelism() {
+    let available = std::thread::available_parallelism().unwrap();
+    assert_eq!(Parallelism::new().selected_jobs(), available);
+}
+
+#[cfg(unix)]
+mod concurrency {
+    use std::path::Path;
+
+    use rustc_to_wasm_compiler::configuration::{Configuration, Filename};
+
+    use super::*;
+    use crate::fakes;
+
+    /// The factorial in a crate root named after `index`, compiled by `rustc`
+    fn numbered(rustc: &Path, index: usize) -> Configuration {
+        fakes::fac_compiled_by(rustc.into())
+            .filename(Filename::Configured(format!("{index}.rs")))
+            .build()
+    }
+
+    fn batch(directory: &Path) -> anyhow::Result<Vec<Configuration>> {
+        let rustc = fakes::counting_rustc(directory)?;
+        Ok((0..8).rev().map(|index| numbered(&rustc, index)).collect())
+    }
+
+    fn max_concurrency(directory: &Path) -> anyhow::Result<usize> {
+        let counts = std::fs::read_to_string(directory.join("counts"))?;
+        Ok(counts
+            .lines()
+            .map(|count| count.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .max()
+            .unwrap_or(0))
+    }
+
+    #[test]
+    fn jobs_bound_concurrency_and_keep_order() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let configurations = batch(directory.path())?;
+        let parallelism = Parallelism::new().jobs(NonZeroUsize::new(2).unwrap());
+
+        let results = Compiler::compile_many(&configurations, &parallelism);
+
+        for (result, index) in results.iter().zip((0..8).rev()) {
+            let Err(Error::Unsuccesful(failure)) = result else {
+                panic!("expected a failed compilation, got {result:?}");
+            };
+            assert!(
+                failure.diagnostics[0]
+                    .message
+                    .ends_with(&format!("{index}.rs"))
+            );
+        }
+        assert_eq!(max_concurrency(directory.path())?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn jobserver_bounds_concurrency() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let configurations = batch(directory.path())?;
+        // One token besides the one this process holds
+        let parallelism = Parallelism::new()
+            .jobs(NonZeroUsize::new(4).unwrap())
+            .jobserver(JobserverClient::new(1)?);
+
+        let results = Compiler::compile_many(&configurations, &parallelism);
+
+        assert_eq!(results.len(), configurations.len());
+        assert_eq!(max_concurrency(directory.path())?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn rustc_inherits_the_jobserver() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::jobserver_probing_rustc(directory.path())?;
+        let configurations = (0..2)
+            .map(|index| numbered(&rustc, index))
+            .collect::<Vec<_>>();
+        let parallelism = Parallelism::new()
+            .jobs(NonZeroUsize::new(2).unwrap())
+            .jobserver(JobserverClient::new(1)?);
+
+        let results = Compiler::compile_many(&configurations, &parallelism);
+        assert!(
+            results
+                .iter()
+                .all(|result| matches!(result, Err(Error::Unsuccesful(_))))
+        );
+
+        let probes = std::fs::read_to_string(directory.path().join("jobservers"))?;
+        assert_eq!(probes.lines().collect::<Vec<_>>(), ["inherited"; 2]);
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::cache::Cache;
+use rustc_to_wasm_compiler::configuration::{Configuration, Profile};
+
+use crate::common::fac_config;
+
+fn config_for(profile: Profile) -> Configuration {
+    fac_config().profile(profile).build()
+}
+
+fn files_in(directory: &std::path::Path) -> usize {
+    std::fs::read_dir(directory).unwrap().count()
+}
+
+#[test]
+fn keys_cover_the_configuration() -> anyhow::Result<()> {
+    let cache = Cache::in_memory();
+
+    let o0 = cache.key(&config_for(Profile::O0))?;
+    assert_eq!(o0, cache.key(&config_for(Profile::O0))?);
+    assert_ne!(o0, cache.key(&config_for(Profile::O3))?);
+
+    // Keys are stable across caches, and thus across processes
+    assert_eq!(o0, Cache::in_memory().key(&config_for(Profile::O0))?);
+    Ok(())
+}
+
+#[test]
+fn disk_hits_return_stored_module() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let config = config_for(Profile::O1);
+
+    let compiled = Compiler::compile_cached(&config, &Cache::on_disk(directory.path().into()))?;
+    assert_eq!(files_in(directory.path()), 2);
+
+    // Tamper with the stored module, a fresh cache should serve it as is
+    let key = Cache::in_memory().key(&config)?;
+    let stored = directory.path().join(format!("{key}.wasm"));
+    assert_eq!(std::fs::read(&stored)?, compiled.wasm());
+    std::fs::write(&stored, b"cached")?;
+
+    let cache = Cache::on_disk(directory.path().into());
+    assert_eq!(Compiler::compile_cached(&config, &cache)?.wasm(), b"cached");
+
+    // Once invalidated, the module is compiled again
+    cache.invalidate(&config)?;
+    assert_eq!(files_in(directory.path()), 0);
+    let recompiled = Compiler::compile_cached(&config, &cache)?;
+    assert_eq!(recompiled.wasm(), compiled.wasm());
+    Ok(())
+}
+
+#[test]
+fn memory_hits_return_stored_module() -> anyhow::Result<()> {
+    let cache = Cache::in_memory();
+    let config = config_for(Profile::O2);
+
+    let first = Compiler::compile_cached(&config, &cache)?;
+    let second = Compiler::compile_cached(&config, &cache)?;
+    assert_eq!(first.wasm(), second.wasm());
+    assert_eq!(first.diagnostics(), second.diagnostics());
+    Ok(())
+}
+
+#[test]
+fn eviction_by_size_and_age() -> anyhow::Result<()> {
+    let by_size = tempfile::TempDir::new()?;
+    let cache = Cache::on_disk(by_size.path().into()).max_size(0);
+    Compiler::compile_cached(&config_for(Profile::O1), &cache)?;
+    assert_eq!(files_in(by_size.path()), 0);
+
+    let by_age = tempfile::TempDir::new()?;
+    let cache = Cache::on_disk(by_age.path().into()).max_age(Duration::from_hours(1));
+    Compiler::compile_cached(&config_for(Profile::O1), &cache)?;
+    assert_eq!(files_in(by_age.path()), 2);
+
+    let cache = Cache::on_disk(by_age.path().into()).max_age(Duration::ZERO);
+    Compiler::compile_cached(&config_for(Profile::O3), &cache)?;
+    assert_eq!(files_in(by_age.path()), 0);
+
+    cache.clear()?;
+    assert_eq!(files_in(by_age.path()), 0);
+    Ok(())
+}
+
+#[test]
+fn only_entries_are_cleared() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let entry = format!("{}.wasm", "0".repeat(64));
+    std::fs::write(directory.path().join(&entry), b"module")?;
+    std::fs::write(directory.path().join("notes.txt"), b"not an entry")?;
+    std::fs::create_dir(directory.path().join(&entry[..64]))?;
+
+    Cache::on_disk(directory.path().into()).clear()?;
+
+    assert!(!directory.path().join(&entry).exists());
+    assert!(directory.path().join("notes.txt").exists());
+    assert!(directory.path().join(&entry[..64]).is_dir());
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::Configuration;
+use rustc_to_wasm_compiler::error::Error;
+use rustc_to_wasm_compiler::process::{Cancellation, Limits};
+
+use crate::fakes;
+
+fn config_for(rustc: PathBuf, limits: Limits) -> Configuration {
+    fakes::fac_compiled_by(rustc).limits(limits).build()
+}
+
+/// Waits for the process `fakes::hanging_rustc` spawned in `directory` to be
+/// killed along with `rustc`, its whole process group is
+#[cfg(target_os = "linux")]
+fn assert_killed(directory: &Path) -> anyhow::Result<()> {
+    let pid = std::fs::read_to_string(directory.join("pid"))?;
+    let pid = pid.trim();
+    for _ in 0..100 {
+        if !fakes::is_running(pid) {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("process {pid} survived the compilation");
+}
+
+#[test]
+fn hanging_compilation_times_out() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let limits = Limits::new().timeout(Duration::from_millis(200));
+    let config = config_for(fakes::hanging_rustc(directory.path())?, limits);
+
+    let start = Instant::now();
+    let result = Compiler::compile(&config);
+
+    let Err(Error::TimedOut(elapsed)) = result else {
+        panic!("expected a timeout, got {result:?}");
+    };
+    assert!(elapsed >= Duration::from_millis(200));
+    // The spawned child holds on to the pipes, it must be killed as well
+    assert!(start.elapsed() < Duration::from_secs(10));
+    #[cfg(target_os = "linux")]
+    assert_killed(directory.path())?;
+    Ok(())
+}
+
+#[test]
+fn hanging_compilation_is_cancelled() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let config = config_for(fakes::hanging_rustc(directory.path())?, Limits::new());
+
+    let cancellation = Cancellation::new();
+    let canceller = cancellation.clone();
+    let start = Instant::now();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        canceller.cancel();
+    });
+    let result = Compiler::compile_cancellable(&config, &cancellation);
+    handle.join().unwrap();
+
+    assert!(matches!(result, Err(Error::Cancelled(_))), "{result:?}");
+    assert!(start.elapsed() < Duration::from_secs(10));
+    #[cfg(target_os = "linux")]
+    assert_killed(directory.path())?;
+    Ok(())
+}
+
+#[test]
+fn cancelled_compilation_does_not_start() {
+    let config = config_for("/nonexistent/bin/rustc".into(), Limits::new());
+
+    let cancellation = Cancellation::new();
+    cancellation.cancel();
+
+    assert!(matches!(
+        Compiler::compile_cancellable(&config, &cancellation),
+        Err(Error::Cancelled(_))
+    ));
+}
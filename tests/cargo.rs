@@ -0,0 +1,154 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::cargo::{CargoConfiguration, CargoProfile, Workspace};
+use rustc_to_wasm_compiler::configuration::{CrateType, ModuleTree};
+use rustc_to_wasm_compiler::error::Error;
+
+const MANIFEST: &str = r#"
+[package]
+name = "greeter"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib"]
+
+[features]
+shout = []
+
+[dependencies]
+helper = { path = "vendor/helper" }
+"#;
+
+const LIB_SOURCE: &str = r#"
+#[no_mangle]
+pub extern "C" fn answer() -> i32 {
+    if cfg!(feature = "shout") {
+        helper::double(helper::ANSWER)
+    } else {
+        helper::ANSWER
+    }
+}
+"#;
+
+const HELPER_MANIFEST: &str = r#"
+[package]
+name = "helper"
+version = "0.1.0"
+edition = "2021"
+"#;
+
+const HELPER_SOURCE: &str = "
+pub const ANSWER: i32 = 21;
+
+pub fn double(n: i32) -> i32 {
+    n * 2
+}
+";
+
+fn workspace() -> Workspace {
+    let mut files = ModuleTree::new();
+    files.insert("src/lib.rs".into(), LIB_SOURCE.into());
+    files.insert("vendor/helper/Cargo.toml".into(), HELPER_MANIFEST.into());
+    files.insert("vendor/helper/src/lib.rs".into(), HELPER_SOURCE.into());
+
+    Workspace::InMemory {
+        manifest: MANIFEST.into(),
+        files,
+    }
+}
+
+fn call_answer(wasm: &[u8]) -> anyhow::Result<i32> {
+    use wasmtime::{Engine, Instance, Module, Store};
+    let engine = Engine::default();
+    let module = Module::from_binary(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let outcome = instance
+        .get_typed_func::<(), i32>(&mut store, "answer")?
+        .call(&mut store, ())?;
+    Ok(outcome)
+}
+
+#[test]
+fn in_memory_workspace_with_vendored_dependency() -> anyhow::Result<()> {
+    let config = CargoConfiguration::new(workspace());
+    let compilation = Compiler::compile_cargo(&config)?;
+
+    assert_eq!(compilation.crate_type(), CrateType::Cdylib);
+    assert_eq!(call_answer(compilation.wasm())?, 21);
+    Ok(())
+}
+
+#[test]
+fn features_are_forwarded() -> anyhow::Result<()> {
+    let config = CargoConfiguration::new(workspace()).feature("shout");
+    let wasm = Compiler::compile_cargo(&config)?.into_wasm();
+
+    assert_eq!(call_answer(&wasm)?, 42);
+    Ok(())
+}
+
+#[test]
+fn profiles_affect() -> anyhow::Result<()> {
+    let dev = CargoConfiguration::new(workspace());
+    let release = CargoConfiguration::new(workspace()).profile(CargoProfile::Release);
+
+    let dev = Compiler::compile_cargo(&dev)?.into_wasm();
+    let release = Compiler::compile_cargo(&release)?.into_wasm();
+
+    assert_ne!(dev.len(), release.len());
+    assert_eq!(call_answer(&release)?, 21);
+    Ok(())
+}
+
+#[test]
+fn directory_workspace() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    std::fs::write(directory.path().join("Cargo.toml"), MANIFEST)?;
+    for (path, contents) in [
+        ("src/lib.rs", LIB_SOURCE),
+        ("vendor/helper/Cargo.toml", HELPER_MANIFEST),
+        ("vendor/helper/src/lib.rs", HELPER_SOURCE),
+    ] {
+        let path = directory.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, contents)?;
+    }
+
+    let config = CargoConfiguration::new(Workspace::Directory(directory.path().into()))
+        .package("greeter")
+        .profile(CargoProfile::Release);
+    let wasm = Compiler::compile_cargo(&config)?.into_wasm();
+
+    assert_eq!(call_answer(&wasm)?, 21);
+    Ok(())
+}
+
+#[test]
+fn renamed_library_of_selected_package() -> anyhow::Result<()> {
+    let Workspace::InMemory { manifest, files } = workspace() else {
+        unreachable!("the workspace is in memory");
+    };
+    let manifest = manifest.replace("[lib]\n", "[lib]\nname = \"renamed\"\n");
+    let config =
+        CargoConfiguration::new(Workspace::InMemory { manifest, files }).package("greeter");
+    let wasm = Compiler::compile_cargo(&config)?.into_wasm();
+
+    assert_eq!(call_answer(&wasm)?, 21);
+    Ok(())
+}
+
+#[test]
+fn invalid_manifest_is_reported() {
+    let config = CargoConfiguration::new(Workspace::InMemory {
+        manifest: "this is not a manifest".into(),
+        files: ModuleTree::new(),
+    });
+
+    let Err(Error::Unsuccesful(failure)) = Compiler::compile_cargo(&config) else {
+        panic!("the build should fail");
+    };
+
+    assert!(!failure.status.success());
+    assert!(failure.unstructured.contains("Cargo.toml"));
+}
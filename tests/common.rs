@@ -0,0 +1,17 @@
+//! Settings shared by the test modules
+
+use rustc_to_wasm_compiler::configuration::{Debugging, Filename, Profile, Source, StackSize};
+use rustc_to_wasm_compiler::configuration_builder::ConfigurationBuilder;
+
+pub const FAC_SOURCE: &str = include_str!("fac.rs_");
+
+/// The factorial, compiled by the default toolchain, for a test to override
+/// the settings it is about
+pub fn fac_config() -> ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename> {
+    ConfigurationBuilder::init()
+        .debugging(Debugging::Disabled)
+        .stack_size(StackSize::Unspecified)
+        .profile(Profile::O1)
+        .source(FAC_SOURCE.into())
+        .filename(Filename::Unspecified)
+}
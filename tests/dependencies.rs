@@ -0,0 +1,84 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, CrateType, Dependencies, Filename};
+use rustc_to_wasm_compiler::error::Error;
+
+use crate::common::fac_config;
+
+const HELPER_SOURCE: &str = "
+    pub fn double(n: i32) -> i32 {
+        n * 2
+    }
+";
+
+const MAIN_SOURCE: &str = r#"
+    extern crate helper;
+
+    #[no_mangle]
+    pub extern "C" fn quadruple(n: i32) -> i32 {
+        helper::double(helper::double(n))
+    }
+"#;
+
+fn helper_rlib() -> Vec<u8> {
+    let config = fac_config()
+        .source(HELPER_SOURCE.into())
+        .filename(Filename::Configured("helper.rs".into()))
+        .crate_type(CrateType::Rlib)
+        .build();
+
+    Compiler::compile(&config).unwrap().into_wasm()
+}
+
+fn config_with(dependencies: Dependencies) -> Configuration {
+    fac_config()
+        .source(MAIN_SOURCE.into())
+        .dependencies(dependencies)
+        .build()
+}
+
+fn call_quadruple(wasm: &[u8]) -> anyhow::Result<i32> {
+    use wasmtime::{Engine, Instance, Module, Store};
+    let engine = Engine::default();
+    let module = Module::from_binary(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let outcome = instance
+        .get_typed_func::<i32, i32>(&mut store, "quadruple")?
+        .call(&mut store, 3)?;
+    Ok(outcome)
+}
+
+#[test]
+fn link_against_in_memory_rlib() -> anyhow::Result<()> {
+    let dependencies = Dependencies::new().extern_bytes("helper", helper_rlib());
+    let wasm = Compiler::compile(&config_with(dependencies))?.into_wasm();
+    assert_eq!(call_quadruple(&wasm)?, 12);
+    Ok(())
+}
+
+#[test]
+fn link_against_rlib_on_disk() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let path = directory.path().join("libhelper.rlib");
+    std::fs::write(&path, helper_rlib())?;
+
+    let dependencies = Dependencies::new()
+        .search_path(directory.path().into())
+        .extern_path("helper", path);
+    let wasm = Compiler::compile(&config_with(dependencies))?.into_wasm();
+    assert_eq!(call_quadruple(&wasm)?, 12);
+    Ok(())
+}
+
+#[test]
+fn missing_dependency_is_reported() {
+    let outcome = Compiler::compile(&config_with(Dependencies::new()));
+    assert!(matches!(outcome, Err(Error::Unsuccesful(_))));
+}
+
+#[test]
+fn extern_names_are_validated() {
+    let dependencies = Dependencies::new().extern_bytes("not-a-crate", vec![]);
+    let outcome = Compiler::compile(&config_with(dependencies));
+    assert!(matches!(outcome, Err(Error::InvalidExternName(name)) if name == "not-a-crate"));
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeSet;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, Emit, Filename};
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+
+use crate::common::fac_config;
+#[cfg(unix)]
+use crate::fakes;
+
+fn config_for(emit: BTreeSet<Emit>, toolchain: Toolchain) -> Configuration {
+    fac_config()
+        .filename(Filename::Configured("fac.rs".into()))
+        .toolchain(toolchain)
+        .emit(emit)
+        .build()
+}
+
+#[test]
+fn no_artifacts_by_default() -> anyhow::Result<()> {
+    let compilation = Compiler::compile(&config_for(BTreeSet::new(), Toolchain::default()))?;
+    assert!(compilation.artifacts().is_empty());
+    Ok(())
+}
+
+#[test]
+fn textual_artifacts_are_returned() -> anyhow::Result<()> {
+    let emit = [Emit::LlvmIr, Emit::Asm, Emit::Mir, Emit::DepInfo].into();
+    let compilation = Compiler::compile(&config_for(emit, Toolchain::default()))?;
+
+    let text = |kind| String::from_utf8(compilation.artifact(kind).unwrap().to_vec());
+    assert!(text(Emit::LlvmIr)?.contains("define"));
+    assert!(text(Emit::Asm)?.contains(".functype"));
+    assert!(text(Emit::Mir)?.contains("fn "));
+    assert!(text(Emit::DepInfo)?.contains("fac.rs"));
+    assert!(compilation.artifact(Emit::Obj).is_none());
+    Ok(())
+}
+
+#[test]
+fn binary_artifacts_are_returned() -> anyhow::Result<()> {
+    let emit = [Emit::LlvmBc, Emit::Obj, Emit::Metadata].into();
+    let compilation = Compiler::compile(&config_for(emit, Toolchain::default()))?;
+
+    assert!(
+        compilation
+            .artifact(Emit::LlvmBc)
+            .unwrap()
+            .starts_with(b"BC")
+    );
+    assert!(
+        compilation
+            .artifact(Emit::Obj)
+            .unwrap()
+            .starts_with(b"\0asm")
+    );
+    // Only libraries for later compilations carry metadata, yet it is emitted
+    assert!(compilation.artifact(Emit::Metadata).is_some());
+    // Emitting artifacts keeps the module
+    assert!(compilation.wasm().starts_with(b"\0asm"));
+    Ok(())
+}
+
+#[cfg(unix)]
+mod fake_rustc {
+    use rustc_to_wasm_compiler::cache::Cache;
+
+    use super::*;
+
+    fn all_kinds() -> BTreeSet<Emit> {
+        [
+            Emit::LlvmIr,
+            Emit::LlvmBc,
+            Emit::Asm,
+            Emit::Mir,
+            Emit::Obj,
+            Emit::DepInfo,
+            Emit::Metadata,
+        ]
+        .into()
+    }
+
+    #[test]
+    fn every_kind_is_read_back() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let toolchain = Toolchain::binary(fakes::emitting_rustc(directory.path())?);
+        let compilation = Compiler::compile(&config_for(all_kinds(), toolchain))?;
+
+        assert_eq!(compilation.wasm(), b"module");
+        assert_eq!(compilation.artifacts().len(), 7);
+        for (kind, artifact) in compilation.artifacts() {
+            assert_eq!(artifact, kind.name().as_bytes());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn artifacts_survive_the_disk_cache() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let cache_directory = tempfile::TempDir::new()?;
+        let toolchain = Toolchain::binary(fakes::emitting_rustc(directory.path())?);
+        let config = config_for(all_kinds(), toolchain);
+
+        let cache = Cache::on_disk(cache_directory.path().into());
+        let compiled = Compiler::compile_cached(&config, &cache)?;
+
+        // A fresh cache only has the disk to serve the artifacts from
+        let cache = Cache::on_disk(cache_directory.path().into());
+        let key = cache.key(&config)?;
+        std::fs::write(cache_directory.path().join(format!("{key}.ll")), b"cached")?;
+        let cached = Compiler::compile_cached(&config, &cache)?;
+        assert_eq!(cached.artifact(Emit::LlvmIr), Some(&b"cached"[..]));
+        assert_eq!(cached.artifact(Emit::Mir), compiled.artifact(Emit::Mir));
+
+        // Removing the entry removes its artifacts as well
+        cache.remove(&key)?;
+        assert_eq!(std::fs::read_dir(cache_directory.path())?.count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, Target};
+use rustc_to_wasm_compiler::environment::EnvironmentProblem;
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+
+use crate::common::fac_config;
+
+fn config_for(toolchain: Toolchain, target: Target) -> Configuration {
+    fac_config().toolchain(toolchain).target(target).build()
+}
+
+#[test]
+fn default_environment_is_ready() {
+    let config = config_for(Toolchain::default(), Target::Wasm32UnknownUnknown);
+    let report = Compiler::check_environment(&config);
+
+    assert!(report.is_ready(), "{:?}", report.problems);
+    assert!(report.toolchain_info.is_some());
+}
+
+#[test]
+fn missing_rustc_is_reported() {
+    let toolchain = Toolchain::binary("/nonexistent/bin/rustc".into());
+    let report = Compiler::check_environment(&config_for(toolchain, Target::Wasm32Wasip1));
+
+    assert!(!report.is_ready());
+    assert!(report.toolchain_info.is_none());
+    let (problem, remediation) = report.remediations().next().unwrap();
+    assert!(matches!(problem, EnvironmentProblem::RustcUnavailable(_)));
+    assert!(remediation.contains("/nonexistent/bin/rustc"));
+}
+
+#[test]
+fn missing_target_and_linkers_are_reported() -> anyhow::Result<()> {
+    let empty_sysroot = tempfile::TempDir::new()?;
+    let toolchain = Toolchain::from_path().sysroot(empty_sysroot.path().into());
+    let report = Compiler::check_environment(&config_for(toolchain, Target::Wasm32Wasip2));
+
+    assert!(!report.is_ready());
+    let remediations = report.remediations().collect::<Vec<_>>();
+    assert_eq!(remediations.len(), 3);
+
+    assert!(matches!(
+        remediations[0].0,
+        EnvironmentProblem::TargetNotInstalled(Target::Wasm32Wasip2)
+    ));
+    assert!(
+        remediations[0]
+            .1
+            .contains("rustup target add wasm32-wasip2")
+    );
+
+    for (problem, _) in &remediations[1..] {
+        assert!(matches!(problem, EnvironmentProblem::LinkerUnavailable(_)));
+    }
+    Ok(())
+}
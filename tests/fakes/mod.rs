@@ -0,0 +1,96 @@
+//! Stand-ins for `rustc`, as shell scripts that behave in the one way a test
+//! needs
+#![allow(dead_code)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use rustc_to_wasm_compiler::configuration::{Debugging, Filename, Profile, Source, StackSize};
+use rustc_to_wasm_compiler::configuration_builder::ConfigurationBuilder;
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+
+use crate::common::fac_config;
+
+/// The factorial, compiled by `rustc`, for a test to complete with the
+/// settings it is about
+pub fn fac_compiled_by(
+    rustc: PathBuf,
+) -> ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename> {
+    fac_config().toolchain(Toolchain::binary(rustc))
+}
+
+/// A `rustc` in `directory` that runs `script` instead of compiling
+pub fn scripted_rustc(directory: &Path, script: &str) -> anyhow::Result<PathBuf> {
+    let rustc = directory.join("rustc");
+    std::fs::write(&rustc, format!("#!/bin/sh\n{script}\n"))?;
+    std::fs::set_permissions(&rustc, std::fs::Permissions::from_mode(0o755))?;
+    Ok(rustc)
+}
+
+/// A `rustc` that produces `module` regardless of its input
+pub fn producing_rustc(directory: &Path, module: &[u8]) -> anyhow::Result<PathBuf> {
+    std::fs::write(directory.join("module.wasm"), module)?;
+    let script = r#"while [ $# -gt 0 ]; do
+    [ "$1" = "-o" ] && cp "$(dirname "$0")/module.wasm" "$2"
+    shift
+done"#;
+    scripted_rustc(directory, script)
+}
+
+/// A `rustc` that writes the name of every requested kind as its artifact
+pub fn emitting_rustc(directory: &Path) -> anyhow::Result<PathBuf> {
+    let script = r#"[ "$1" = "--version" ] && echo "rustc 0.0.0 (fake)" && exit 0
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --emit=link) ;;
+        --emit=*) spec="${1#--emit=}"; printf '%s' "${spec%%=*}" > "${spec#*=}" ;;
+        -o) shift; printf 'module' > "$1" ;;
+    esac
+    shift
+done"#;
+    scripted_rustc(directory, script)
+}
+
+/// A `rustc` that reports its input and records how many instances run at
+/// the same time, the later inputs finishing before the earlier ones
+pub fn counting_rustc(directory: &Path) -> anyhow::Result<PathBuf> {
+    let script = r#"d="$(dirname "$0")"
+touch "$d/running.$$"
+ls "$d" | grep -c '^running\.' >> "$d/counts"
+sleep "0.$(basename "$1" .rs)"
+rm "$d/running.$$"
+echo "{\"message\":\"$1\",\"code\":null,\"level\":\"error\",\"spans\":[],\"children\":[],\"rendered\":null}" >&2
+exit 1"#;
+    scripted_rustc(directory, script)
+}
+
+/// A `rustc` that records whether it can reach the jobserver it was handed,
+/// then fails
+pub fn jobserver_probing_rustc(directory: &Path) -> anyhow::Result<PathBuf> {
+    let script = r#"d="$(dirname "$0")"
+auth="${CARGO_MAKEFLAGS##*--jobserver-auth=}"
+auth="${auth%% *}"
+if ( : <&"${auth%%,*}" && : >&"${auth##*,}" ) 2>/dev/null; then
+    echo inherited >> "$d/jobservers"
+else
+    echo missing >> "$d/jobservers"
+fi
+exit 1"#;
+    scripted_rustc(directory, script)
+}
+
+/// A `rustc` that hangs in a process of its own, like a linker would, and
+/// records the id of that process next to itself
+pub fn hanging_rustc(directory: &Path) -> anyhow::Result<PathBuf> {
+    scripted_rustc(
+        directory,
+        "sleep 60 &\necho $! > \"$(dirname \"$0\")/pid\"\nwait",
+    )
+}
+
+/// Whether the process is still running, zombies awaiting reaping are not
+#[cfg(target_os = "linux")]
+pub fn is_running(pid: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .is_ok_and(|stat| stat.split(' ').nth(2).is_none_or(|state| state != "Z"))
+}
@@ -8,8 +8,40 @@ use rustc_to_wasm_compiler::configuration::{
 use rustc_to_wasm_compiler::configuration_builder::ConfigurationBuilder;
 use wasmtime::{WasmParams, WasmResults};
 
+mod common;
+#[cfg(unix)]
+mod fakes;
 mod mocked_fs;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod batch;
+mod cache;
+#[cfg(unix)]
+mod cancellation;
+mod cargo;
+mod dependencies;
+mod emit;
+mod environment;
+mod memory_layout;
+mod module_info;
+mod module_tree;
+mod required_exports;
+#[cfg(target_os = "linux")]
+mod resource_limits;
+#[cfg(feature = "runtime")]
+mod runtime;
+mod source_map;
+mod symbolication;
+mod target_features;
+mod targets;
+mod toolchain;
+#[cfg(feature = "runtime")]
+mod wasi;
+mod wat;
+
+use common::{FAC_SOURCE, fac_config};
+
 const PROFILE_OPTS: &[Profile; 4] = {
     use Profile::{O0, O1, O2, O3};
     &[O0, O1, O2, O3]
@@ -46,8 +78,6 @@ fn test_semver() -> anyhow::Result<()> {
     Ok(())
 }
 
-const FAC_SOURCE: &str = include_str!("fac.rs_");
-
 #[test]
 fn test_different_variants() {
     FILENAME_CONFIGS.par_iter().for_each(|filename_config| {
@@ -118,6 +148,66 @@ fn unsafe_c_overflow() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn structured_diagnostics() {
+    use rustc_to_wasm_compiler::diagnostic::Level;
+    use rustc_to_wasm_compiler::error::Error;
+
+    let source = r#"
+        #[no_mangle]
+        pub extern "C" fn fac(n: i32) -> i32 {
+            n * undefined_fac(n - 1)
+        }
+    "#;
+
+    let config = fac_config()
+        .profile(Profile::O0)
+        .source(source.into())
+        .filename(Filename::Configured("lib.rs".into()))
+        .build();
+
+    let Err(Error::Unsuccesful(failure)) = Compiler::compile(&config) else {
+        panic!("compilation should fail with diagnostics");
+    };
+
+    let error = failure
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.level == Level::Error && diagnostic.code.is_some())
+        .unwrap();
+
+    assert_eq!(error.code.as_ref().unwrap().code, "E0425");
+    let span = error.primary_span().unwrap();
+    assert_eq!(span.file_name, "lib.rs");
+    assert_eq!((span.line_start, span.column_start), (4, 17));
+    assert_eq!((span.line_end, span.column_end), (4, 30));
+}
+
+#[test]
+fn warnings_are_returned() {
+    let source = r#"
+        #[no_mangle]
+        pub extern "C" fn double(n: i32) -> i32 {
+            let unused = n;
+            n * 2
+        }
+    "#;
+
+    let config = fac_config()
+        .profile(Profile::O0)
+        .source(source.into())
+        .build();
+
+    let compilation = Compiler::compile(&config).unwrap();
+    assert!(!compilation.wasm().is_empty());
+
+    let unused = compilation
+        .warnings()
+        .find(|warning| warning.message.contains("unused variable"))
+        .unwrap();
+    assert_eq!(unused.primary_span().unwrap().line_start, 4);
+}
+
 #[test]
 fn failing_compilation() {
     let config = ConfigurationBuilder::init()
@@ -134,6 +224,23 @@ fn failing_compilation() {
     );
 }
 
+#[test]
+fn unstructured_stderr_is_kept_apart() {
+    let warning = r#"{"$message_type":"diagnostic","message":"unused variable","code":null,"level":"warning","spans":[],"children":[],"rendered":null}"#;
+    let artifact = r#"{"$message_type":"artifact","artifact":"out.wasm","emit":"link"}"#;
+    let stderr = format!(
+        "thread 'rustc' panicked at compiler/rustc_codegen_llvm/src/lib.rs:1:1\n{warning}\n{artifact}\nnote: rustc unexpectedly panicked\n"
+    );
+
+    let report = rustc_to_wasm_compiler::diagnostic::parse(stderr.as_bytes());
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].message, "unused variable");
+    assert_eq!(
+        report.unstructured,
+        "thread 'rustc' panicked at compiler/rustc_codegen_llvm/src/lib.rs:1:1\nnote: rustc unexpectedly panicked\n"
+    );
+}
+
 fn assert_outcome<Params: WasmParams, Results: WasmResults + Eq + Debug>(
     config: &Configuration,
     exported: &str,
@@ -142,7 +249,7 @@ fn assert_outcome<Params: WasmParams, Results: WasmResults + Eq + Debug>(
 ) -> anyhow::Result<()> {
     let output = {
         /* Compiling Rust source code into Wasm */
-        Compiler::compile(config)?
+        Compiler::compile(config)?.into_wasm()
     };
 
     {
@@ -178,7 +285,7 @@ fn debugging_info_included() {
             .debugging(debug)
             .profile(profile)
             .build();
-        let debuggable = Compiler::compile(&debuggable).unwrap();
+        let debuggable = Compiler::compile(&debuggable).unwrap().into_wasm();
         let debuggable = Module::new(debuggable);
         let debug_files = debuggable.files().unwrap();
         debug_files.len()
@@ -199,7 +306,7 @@ fn optimizations_affect() {
     let module_len_for = |profile| {
         let config = partial_config.clone().profile(profile).build();
         let module = Compiler::compile(&config).unwrap();
-        module.len()
+        module.wasm().len()
     };
 
     let profiles = [Profile::O0, Profile::O1, Profile::O2, Profile::O3];
@@ -220,7 +327,7 @@ fn stack_size_configuration_affect() {
     let module_len_for = |stacksize| {
         let config = partial_config.clone().stack_size(stacksize).build();
         let module = Compiler::compile(&config).unwrap();
-        module.len()
+        module.wasm().len()
     };
 
     let profiles = [StackSize::Unspecified, StackSize::Configured(2_u32.pow(4))];
@@ -242,3 +349,44 @@ fn configuration_settings() {
     assert_eq!(config.profile(), &Profile::O0);
     assert_eq!(config.source(), "hi there!");
 }
+
+#[cfg(unix)]
+mod fake_rustc {
+    use rustc_to_wasm_compiler::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn unstructured_stderr_does_not_fail_compilations() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let script = r#"echo "rust-lld: warning: unstructured linker output" >&2
+while [ $# -gt 0 ]; do
+    [ "$1" = "-o" ] && printf '\0asm\1\0\0\0' > "$2"
+    shift
+done"#;
+        let rustc = fakes::scripted_rustc(directory.path(), script)?;
+
+        let compilation = Compiler::compile(&fakes::fac_compiled_by(rustc).build())?;
+        assert_eq!(compilation.wasm(), b"\0asm\x01\0\0\0");
+        Ok(())
+    }
+
+    #[test]
+    fn internal_compiler_errors_keep_their_report() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let script = r#"echo "thread 'rustc' panicked at compiler/src/lib.rs:1:1" >&2
+exit 101"#;
+        let rustc = fakes::scripted_rustc(directory.path(), script)?;
+
+        let Err(Error::Unsuccesful(failure)) =
+            Compiler::compile(&fakes::fac_compiled_by(rustc).build())
+        else {
+            panic!("compilation should fail");
+        };
+        assert_eq!(failure.status.code(), Some(101));
+        assert!(failure.diagnostics.is_empty());
+        assert!(failure.unstructured.contains("panicked"));
+        assert!(failure.to_string().contains("panicked"));
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{
+    Configuration, MemoryLayout, PAGE_SIZE, TargetFeature, TargetFeatures,
+};
+use rustc_to_wasm_compiler::error::{Error, MemoryLayoutError};
+use wasmparser::{Parser, Payload, TypeRef};
+
+use crate::common::fac_config;
+
+fn config_with(memory_layout: MemoryLayout, target_features: TargetFeatures) -> Configuration {
+    fac_config()
+        .memory_layout(memory_layout)
+        .target_features(target_features)
+        .build()
+}
+
+fn rejected_with(memory_layout: MemoryLayout) -> MemoryLayoutError {
+    let config = config_with(memory_layout, TargetFeatures::new());
+    match Compiler::compile(&config) {
+        Err(Error::InvalidMemoryLayout(error)) => error,
+        other => panic!("expected an invalid memory layout, got {other:?}"),
+    }
+}
+
+#[test]
+fn layout_validation() {
+    assert_eq!(
+        rejected_with(MemoryLayout::new().initial_memory(PAGE_SIZE + 1)),
+        MemoryLayoutError::NotPageAligned("initial memory", PAGE_SIZE + 1)
+    );
+    assert_eq!(
+        rejected_with(MemoryLayout::new().max_memory(PAGE_SIZE * 65537)),
+        MemoryLayoutError::ExceedsAddressSpace("max memory", PAGE_SIZE * 65537)
+    );
+    assert_eq!(
+        rejected_with(
+            MemoryLayout::new()
+                .initial_memory(PAGE_SIZE * 4)
+                .max_memory(PAGE_SIZE * 2)
+        ),
+        MemoryLayoutError::InitialExceedsMax(PAGE_SIZE * 4, PAGE_SIZE * 2)
+    );
+    assert_eq!(
+        rejected_with(
+            MemoryLayout::new()
+                .initial_memory(PAGE_SIZE)
+                .global_base(PAGE_SIZE)
+        ),
+        MemoryLayoutError::GlobalBaseOutsideMemory(PAGE_SIZE, PAGE_SIZE)
+    );
+    assert_eq!(
+        rejected_with(MemoryLayout::new().shared_memory()),
+        MemoryLayoutError::SharedWithoutMax
+    );
+    assert_eq!(
+        rejected_with(
+            MemoryLayout::new()
+                .max_memory(PAGE_SIZE * 32)
+                .shared_memory()
+        ),
+        MemoryLayoutError::SharedWithoutAtomics
+    );
+}
+
+#[test]
+fn shared_memory_accepted_with_atomics() {
+    let layout = MemoryLayout::new()
+        .max_memory(PAGE_SIZE * 32)
+        .shared_memory();
+    let features = TargetFeatures::new().enable(TargetFeature::Atomics);
+    assert_eq!(layout.validate(&features), Ok(()));
+}
+
+#[test]
+fn imported_memory_with_limits() -> anyhow::Result<()> {
+    let layout = MemoryLayout::new()
+        .import_memory()
+        .initial_memory(PAGE_SIZE * 20)
+        .max_memory(PAGE_SIZE * 40);
+    let config = config_with(layout, TargetFeatures::new());
+    let wasm = Compiler::compile(&config)?.into_wasm();
+
+    let mut imported_memory = None;
+    for payload in Parser::new(0).parse_all(&wasm) {
+        if let Payload::ImportSection(imports) = payload? {
+            for import in imports {
+                let import = import?;
+                if let TypeRef::Memory(memory) = import.ty {
+                    imported_memory = Some((import.module.to_string(), memory));
+                }
+            }
+        }
+    }
+
+    let (module, memory) = imported_memory.expect("memory should be imported");
+    assert_eq!(module, "env");
+    assert_eq!(memory.initial, 20);
+    assert_eq!(memory.maximum, Some(40));
+    Ok(())
+}
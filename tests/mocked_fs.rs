@@ -9,7 +9,7 @@ use std::path::PathBuf;
 
 static mut FAIL_COUNTER: i32 = 10;
 
-const FAC_SOURCE: &str = include_str!("fac.rs_");
+use crate::common::FAC_SOURCE;
 
 struct MockFS;
 
@@ -26,6 +26,16 @@ impl FileOps for MockFS {
         }
     }
 
+    fn create_file_all(path: &std::path::Path) -> std::io::Result<File> {
+        if unsafe { FAIL_COUNTER == 0 } {
+            Err(std::io::Error::from_raw_os_error(0))
+        } else {
+            unsafe { FAIL_COUNTER -= 1 }
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            File::create(path)
+        }
+    }
+
     fn write_all(file: &mut File, data: &[u8]) -> std::io::Result<()> {
         use std::io::Write;
         if unsafe { FAIL_COUNTER == 0 } {
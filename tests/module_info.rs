@@ -0,0 +1,130 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::error::ModuleInfoError;
+use rustc_to_wasm_compiler::module_info::{
+    ExternType, FunctionType, GlobalType, MemoryType, ModuleInfo, TableType, ValueType,
+};
+
+use crate::common::fac_config;
+
+#[test]
+fn fac_is_exported() -> anyhow::Result<()> {
+    let info = Compiler::compile(&fac_config().build())?.module_info()?;
+
+    assert_eq!(
+        info.exported_function("fac"),
+        Some(&FunctionType {
+            params: vec![ValueType::I32],
+            results: vec![ValueType::I32],
+        })
+    );
+    assert!(matches!(
+        info.export("memory").map(|export| &export.ty),
+        Some(ExternType::Memory(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn imports_and_exports_are_typed() -> anyhow::Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module
+            (import "env" "log" (func $log (param i64)))
+            (import "env" "table" (table 1 funcref))
+            (memory (export "memory") 2 16)
+            (global $counter (export "counter") (mut i32) (i32.const 0))
+            (func (export "tick") (result f64)
+                (call $log (i64.const 1))
+                (f64.const 0))
+            (@custom "notes" "hello"))"#,
+    )?;
+    let info = ModuleInfo::parse(&wasm)?;
+
+    assert_eq!(info.imports.len(), 2);
+    assert_eq!(info.imports[0].module, "env");
+    assert_eq!(info.imports[0].name, "log");
+    assert_eq!(
+        info.imports[0].ty,
+        ExternType::Function(FunctionType {
+            params: vec![ValueType::I64],
+            results: vec![],
+        })
+    );
+    assert_eq!(
+        info.imports[1].ty,
+        ExternType::Table(TableType {
+            element: ValueType::Ref("funcref".into()),
+            initial: 1,
+            maximum: None,
+        })
+    );
+
+    // The exported function is the second one, after the import
+    assert_eq!(
+        info.exported_function("tick"),
+        Some(&FunctionType {
+            params: vec![],
+            results: vec![ValueType::F64],
+        })
+    );
+    assert_eq!(info.exported_function("memory"), None);
+
+    let memory = MemoryType {
+        initial: 2,
+        maximum: Some(16),
+        shared: false,
+        memory64: false,
+    };
+    assert_eq!(info.memories, vec![memory.clone()]);
+    assert_eq!(
+        info.export("memory").map(|export| &export.ty),
+        Some(&ExternType::Memory(memory))
+    );
+    assert_eq!(
+        info.globals,
+        vec![GlobalType {
+            value: ValueType::I32,
+            mutable: true,
+        }]
+    );
+    assert!(info.tables.is_empty());
+    Ok(())
+}
+
+#[test]
+fn sections_are_measured() -> anyhow::Result<()> {
+    let wasm = wat::parse_str(r#"(module (func) (@custom "notes" "hello"))"#)?;
+    let info = ModuleInfo::parse(&wasm)?;
+
+    assert_eq!(info.size, wasm.len());
+    let names: Vec<_> = info.sections.iter().map(|section| &*section.name).collect();
+    assert!(names.contains(&"type"));
+    assert!(names.contains(&"code"));
+
+    let notes = info
+        .custom_sections
+        .iter()
+        .find(|section| section.name == "notes")
+        .unwrap();
+    // The name, prefixed by its length, and the contents
+    assert_eq!(notes.size, 1 + "notes".len() + "hello".len());
+    assert!(info.sections.contains(notes));
+    Ok(())
+}
+
+#[test]
+fn garbage_is_unreadable() {
+    assert!(matches!(
+        ModuleInfo::parse(b"not a module"),
+        Err(ModuleInfoError::Unreadable(_))
+    ));
+}
+
+#[test]
+fn components_are_rejected() -> anyhow::Result<()> {
+    let wasm = wat::parse_str("(component)")?;
+    assert!(matches!(
+        ModuleInfo::parse(&wasm),
+        Err(ModuleInfoError::Component)
+    ));
+    Ok(())
+}
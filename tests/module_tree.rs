@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, Filename, ModuleTree};
+use rustc_to_wasm_compiler::error::Error;
+
+use crate::common::fac_config;
+
+const ROOT_SOURCE: &str = r#"
+    mod math;
+    mod util;
+
+    #[no_mangle]
+    pub extern "C" fn fac_plus_greeting(n: i32) -> i32 {
+        math::fac(n) + util::greeting_len()
+    }
+"#;
+
+fn config_with(modules: ModuleTree) -> Configuration {
+    fac_config()
+        .source(ROOT_SOURCE.into())
+        .filename(Filename::Configured("lib.rs".into()))
+        .modules(modules)
+        .build()
+}
+
+#[test]
+fn modules_and_includes_resolve() -> anyhow::Result<()> {
+    use wasmtime::{Engine, Instance, Module, Store};
+
+    let mut modules = ModuleTree::new();
+    modules.insert(
+        "math.rs".into(),
+        b"pub fn fac(n: i32) -> i32 { if n == 0 { 1 } else { n * fac(n - 1) } }".to_vec(),
+    );
+    modules.insert(
+        "util/mod.rs".into(),
+        br#"pub fn greeting_len() -> i32 { include_str!("greeting.txt").len() as i32 }"#.to_vec(),
+    );
+    modules.insert("util/greeting.txt".into(), b"hello".to_vec());
+
+    let wasm = Compiler::compile(&config_with(modules))?.into_wasm();
+
+    let engine = Engine::default();
+    let module = Module::from_binary(&engine, &wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let outcome = instance
+        .get_typed_func::<i32, i32>(&mut store, "fac_plus_greeting")?
+        .call(&mut store, 5)?;
+
+    assert_eq!(outcome, 125);
+    Ok(())
+}
+
+#[test]
+fn module_paths_stay_in_crate() {
+    let escaping = [
+        "../math.rs",
+        "/tmp/math.rs",
+        "util/../../math.rs",
+        "lib.rs",
+        "",
+    ];
+
+    for path in escaping {
+        let mut modules = ModuleTree::new();
+        modules.insert(path.into(), vec![]);
+
+        let outcome = Compiler::compile(&config_with(modules));
+        assert!(
+            matches!(&outcome, Err(Error::InvalidModulePath(invalid)) if invalid == &PathBuf::from(path)),
+            "{path:?} should be rejected, got {outcome:?}"
+        );
+    }
+}
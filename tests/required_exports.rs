@@ -0,0 +1,152 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::RequiredExports;
+use rustc_to_wasm_compiler::error::{Error, ExportError};
+use rustc_to_wasm_compiler::module_info::{ExternType, FunctionType, ValueType};
+
+use crate::common::{FAC_SOURCE, fac_config};
+#[cfg(unix)]
+use crate::fakes;
+
+fn i32_to_i32() -> FunctionType {
+    FunctionType {
+        params: vec![ValueType::I32],
+        results: vec![ValueType::I32],
+    }
+}
+
+#[test]
+fn fac_is_verified() -> anyhow::Result<()> {
+    let required = RequiredExports::new()
+        .function("fac", i32_to_i32())
+        .export("memory");
+    Compiler::compile(&fac_config().required_exports(required).build())?;
+    Ok(())
+}
+
+#[test]
+fn forgotten_no_mangle_is_reported() {
+    let source = FAC_SOURCE.replace("#[no_mangle]\n", "");
+    let required = RequiredExports::new().function("fac", i32_to_i32());
+
+    let config = fac_config()
+        .source(source)
+        .required_exports(required)
+        .build();
+    let result = Compiler::compile(&config);
+    let Err(Error::RequiredExport(error)) = result else {
+        panic!("expected a missing export, got {result:?}");
+    };
+    assert!(matches!(*error, ExportError::Missing(ref name, _) if name == "fac"));
+}
+
+#[cfg(unix)]
+mod fake_rustc {
+    use rustc_to_wasm_compiler::configuration::CrateType;
+
+    use super::*;
+
+    /// `fac` without `#[no_mangle]`, kept alive by the exported `run`
+    const MANGLED: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func $_ZN20rustc_to_wasm_source3fac17h0123456789abcdefE (param i32) (result i32)
+            local.get 0)
+          (func $run (export "run") (result i32)
+            (call $_ZN20rustc_to_wasm_source3fac17h0123456789abcdefE (i32.const 5))))
+    "#;
+
+    fn compile(
+        module: &[u8],
+        required: RequiredExports,
+    ) -> anyhow::Result<Result<(), Box<ExportError>>> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), module)?;
+        let config = fakes::fac_compiled_by(rustc)
+            .required_exports(required)
+            .build();
+        Ok(match Compiler::compile(&config) {
+            Ok(_) => Ok(()),
+            Err(Error::RequiredExport(error)) => Err(error),
+            Err(error) => return Err(error.into()),
+        })
+    }
+
+    #[test]
+    fn present_exports_pass() -> anyhow::Result<()> {
+        let required = RequiredExports::new()
+            .function(
+                "run",
+                FunctionType {
+                    params: vec![],
+                    results: vec![ValueType::I32],
+                },
+            )
+            .export("memory");
+        assert!(compile(&wat::parse_str(MANGLED)?, required)?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_exports_hint_at_mangled_functions() -> anyhow::Result<()> {
+        let required = RequiredExports::new().function("fac", i32_to_i32());
+        let error = compile(&wat::parse_str(MANGLED)?, required)?.unwrap_err();
+
+        assert!(matches!(
+            *error,
+            ExportError::Missing(ref name, Some(ref mangled))
+                if name == "fac" && mangled == "_ZN20rustc_to_wasm_source3fac17h0123456789abcdefE"
+        ));
+        assert!(error.to_string().contains("`#[no_mangle]` missing"));
+        Ok(())
+    }
+
+    #[test]
+    fn similar_names_are_not_hinted() -> anyhow::Result<()> {
+        // `fa` and `facto` are not `fac`
+        let required = RequiredExports::new().export("fa").export("facto");
+        let error = compile(&wat::parse_str(MANGLED)?, required)?.unwrap_err();
+
+        assert!(matches!(*error, ExportError::Missing(ref name, None) if name == "fa"));
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched_signatures_are_reported() -> anyhow::Result<()> {
+        let required = RequiredExports::new()
+            .function("run", i32_to_i32())
+            .function("memory", i32_to_i32());
+        let error = compile(&wat::parse_str(MANGLED)?, required)?.unwrap_err();
+
+        assert!(matches!(
+            *error,
+            ExportError::Mismatched(ref name, _, ExternType::Function(ref found))
+                if name == "run" && found.params.is_empty()
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn non_functions_are_mismatched() -> anyhow::Result<()> {
+        let required = RequiredExports::new().function("memory", i32_to_i32());
+        let error = compile(&wat::parse_str(MANGLED)?, required)?.unwrap_err();
+
+        assert!(matches!(
+            *error,
+            ExportError::Mismatched(_, _, ExternType::Memory(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn libraries_are_not_verified() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), b"!<arch>\n")?;
+        let config = fakes::fac_compiled_by(rustc)
+            .crate_type(CrateType::Rlib)
+            .required_exports(RequiredExports::new().export("fac"))
+            .build();
+
+        Compiler::compile(&config)?;
+        Ok(())
+    }
+}
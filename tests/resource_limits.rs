@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::error::Error;
+use rustc_to_wasm_compiler::process::Limits;
+
+use crate::common::fac_config;
+use crate::fakes;
+
+#[test]
+fn memory_limit_is_reported() {
+    let limits = Limits::new().memory(64 * 1024 * 1024);
+    let config = fac_config().limits(limits).build();
+
+    assert!(matches!(
+        Compiler::compile(&config),
+        Err(Error::MemoryLimitExceeded(bytes)) if bytes == 64 * 1024 * 1024
+    ));
+}
+
+#[test]
+fn cpu_time_limit_is_reported() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let rustc = fakes::scripted_rustc(directory.path(), "while :; do :; done")?;
+    let limits = Limits::new()
+        .cpu_time(Duration::from_secs(1))
+        .timeout(Duration::from_mins(1));
+    let config = fakes::fac_compiled_by(rustc).limits(limits).build();
+
+    let result = Compiler::compile(&config);
+    assert!(
+        matches!(result, Err(Error::CpuTimeLimitExceeded(_))),
+        "{result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn file_size_limit_is_reported() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let rustc = fakes::scripted_rustc(
+        directory.path(),
+        r#"exec head -c 100000 /dev/zero > "$(dirname "$0")/out""#,
+    )?;
+    let limits = Limits::new().file_size(1000);
+    let config = fakes::fac_compiled_by(rustc).limits(limits).build();
+
+    assert!(matches!(
+        Compiler::compile(&config),
+        Err(Error::FileSizeLimitExceeded(1000))
+    ));
+    Ok(())
+}
+
+#[test]
+fn compile_errors_within_limits_are_not_violations() {
+    let limits = Limits::new()
+        .memory(8 * 1024 * 1024 * 1024)
+        .cpu_time(Duration::from_mins(1))
+        .file_size(64 * 1024 * 1024);
+    let config = fac_config()
+        .source("fn broken(".into())
+        .limits(limits)
+        .build();
+
+    assert!(matches!(
+        Compiler::compile(&config),
+        Err(Error::Unsuccesful(_))
+    ));
+}
+
+/// Text that is reported when running into each of the limits
+const VIOLATION_REPORTS: &str = "out of memory, memory allocation of, File too large, \
+    os error 27, Resource temporarily unavailable, SIGXCPU, SIGXFSZ";
+
+/// Every limit, each too generous to run into
+fn all_limits() -> Limits {
+    let mut processes = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `getrlimit` only writes the limit that is passed
+    assert_eq!(
+        unsafe { libc::getrlimit(libc::RLIMIT_NPROC, &raw mut processes) },
+        0
+    );
+
+    Limits::new()
+        .memory(8 * 1024 * 1024 * 1024)
+        .cpu_time(Duration::from_mins(1))
+        .file_size(64 * 1024 * 1024)
+        .processes(processes.rlim_max)
+}
+
+#[test]
+fn sources_quoting_violations_are_compile_errors() {
+    let source = format!("compile_error!(\"{VIOLATION_REPORTS}\");");
+    let config = fac_config().source(source).limits(all_limits()).build();
+
+    let result = Compiler::compile(&config);
+    assert!(matches!(result, Err(Error::Unsuccesful(_))), "{result:?}");
+}
+
+#[test]
+fn diagnostics_quoting_violations_are_compile_errors() -> anyhow::Result<()> {
+    let directory = tempfile::TempDir::new()?;
+    let diagnostic = serde_json::json!({
+        "$message_type": "diagnostic",
+        "level": "error",
+        "code": null,
+        "message": VIOLATION_REPORTS,
+        "spans": [],
+        "children": [],
+        "rendered": VIOLATION_REPORTS,
+    });
+    let rustc = fakes::scripted_rustc(
+        directory.path(),
+        &format!("echo '{diagnostic}' >&2\nexit 1"),
+    )?;
+    let config = fakes::fac_compiled_by(rustc).limits(all_limits()).build();
+
+    let result = Compiler::compile(&config);
+    assert!(matches!(result, Err(Error::Unsuccesful(_))), "{result:?}");
+    Ok(())
+}
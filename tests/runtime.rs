@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::error::RuntimeError;
+use rustc_to_wasm_compiler::runtime::wasmtime::Trap;
+use rustc_to_wasm_compiler::runtime::{CompiledModule, ExecutionLimits};
+
+use crate::common::fac_config;
+
+const SPIN: &str = r#"
+    (module
+      (func (export "spin")
+        (loop $forever (br $forever)))
+      (func (export "count") (param $n i32) (result i32)
+        (local $i i32)
+        (block $done
+          (loop $next
+            (br_if $done (i32.ge_s (local.get $i) (local.get $n)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $next)))
+        (local.get $i)))
+"#;
+
+fn module(wat: &str, limits: ExecutionLimits) -> anyhow::Result<CompiledModule> {
+    Ok(CompiledModule::new(&wat::parse_str(wat)?, limits)?)
+}
+
+#[test]
+fn fac_is_called() -> anyhow::Result<()> {
+    let module =
+        Compiler::compile(&fac_config().build())?.compiled_module(ExecutionLimits::new())?;
+
+    assert_eq!(module.call::<i32, i32>("fac", 5)?, 120);
+    Ok(())
+}
+
+#[test]
+fn instances_keep_their_state() -> anyhow::Result<()> {
+    let module = module(
+        r#"(module
+            (global $total (mut i32) (i32.const 0))
+            (func (export "add") (param i32) (result i32)
+              (global.set $total (i32.add (global.get $total) (local.get 0)))
+              (global.get $total)))"#,
+        ExecutionLimits::new(),
+    )?;
+    let mut instance = module.instantiate()?;
+
+    assert_eq!(instance.call::<i32, i32>("add", 2)?, 2);
+    assert_eq!(instance.call::<i32, i32>("add", 3)?, 5);
+    // A fresh instance starts over
+    assert_eq!(module.call::<i32, i32>("add", 3)?, 3);
+    Ok(())
+}
+
+#[test]
+fn traps_are_typed() -> anyhow::Result<()> {
+    let module = module(
+        r#"(module
+            (func (export "fail") unreachable)
+            (func (export "divide") (param i32 i32) (result i32)
+              (i32.div_s (local.get 0) (local.get 1))))"#,
+        ExecutionLimits::new(),
+    )?;
+
+    assert!(matches!(
+        module.call::<(), ()>("fail", ()),
+        Err(RuntimeError::Trap(Trap::UnreachableCodeReached, _))
+    ));
+    assert!(matches!(
+        module.call::<(i32, i32), i32>("divide", (1, 0)),
+        Err(RuntimeError::Trap(Trap::IntegerDivisionByZero, _))
+    ));
+    Ok(())
+}
+
+#[test]
+fn exports_are_checked() -> anyhow::Result<()> {
+    let module = module(SPIN, ExecutionLimits::new())?;
+
+    assert!(matches!(
+        module.call::<(), ()>("missing", ()),
+        Err(RuntimeError::UnknownExport(name)) if name == "missing"
+    ));
+    assert!(matches!(
+        module.call::<i64, i64>("count", 1),
+        Err(RuntimeError::Signature(name, _)) if name == "count"
+    ));
+    Ok(())
+}
+
+#[test]
+fn fuel_is_limited_per_call() -> anyhow::Result<()> {
+    let module = module(SPIN, ExecutionLimits::new().fuel(10_000))?;
+
+    assert!(matches!(
+        module.call::<(), ()>("spin", ()),
+        Err(RuntimeError::Trap(Trap::OutOfFuel, _))
+    ));
+
+    let mut instance = module.instantiate()?;
+    for _ in 0..3 {
+        assert_eq!(instance.call::<i32, i32>("count", 100)?, 100);
+    }
+    assert!(instance.remaining_fuel().is_some_and(|fuel| fuel < 10_000));
+    Ok(())
+}
+
+#[test]
+fn epoch_deadline_interrupts() -> anyhow::Result<()> {
+    let module = Arc::new(module(SPIN, ExecutionLimits::new().epoch_deadline(1))?);
+
+    let ticker = {
+        let module = Arc::clone(&module);
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            module.increment_epoch();
+        })
+    };
+
+    assert!(matches!(
+        module.call::<(), ()>("spin", ()),
+        Err(RuntimeError::Trap(Trap::Interrupt, _))
+    ));
+    ticker.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn host_imports_are_provided() -> anyhow::Result<()> {
+    const LOGGING: &str = r#"
+        (module
+          (import "env" "log" (func $log (param i32)))
+          (func (export "run")
+            (call $log (i32.const 42))))
+    "#;
+
+    let logged = Arc::new(AtomicI32::new(0));
+    let mut module = module(LOGGING, ExecutionLimits::new())?;
+
+    // Without the import, the module cannot be instantiated
+    assert!(matches!(module.instantiate(), Err(RuntimeError::Link(_))));
+
+    let sink = Arc::clone(&logged);
+    module.func_wrap("env", "log", move |value: i32| {
+        sink.store(value, Ordering::SeqCst);
+    })?;
+    module.call::<(), ()>("run", ())?;
+
+    assert_eq!(logged.load(Ordering::SeqCst), 42);
+    Ok(())
+}
+
+#[test]
+fn invalid_modules_are_reported() {
+    assert!(matches!(
+        CompiledModule::new(b"not a module", ExecutionLimits::new()),
+        Err(RuntimeError::Module(_))
+    ));
+}
@@ -0,0 +1,218 @@
+use base64::Engine as _;
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, Debugging, Filename, SourceMap};
+use rustc_to_wasm_compiler::error::Error;
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+use wasmparser::{BinaryReader, Parser, Payload};
+
+use crate::common::{FAC_SOURCE, fac_config};
+#[cfg(unix)]
+use crate::fakes;
+
+fn config_for(debugging: Debugging, source_map: SourceMap, toolchain: Toolchain) -> Configuration {
+    fac_config()
+        .debugging(debugging)
+        .filename(Filename::Configured("fac.rs".into()))
+        .toolchain(toolchain)
+        .source_map(source_map)
+        .build()
+}
+
+/// The URL in the `sourceMappingURL` section of a module, if it has one
+fn source_mapping_url(wasm: &[u8]) -> anyhow::Result<Option<String>> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CustomSection(custom) = payload?
+            && custom.name() == "sourceMappingURL"
+        {
+            let mut reader = BinaryReader::new(custom.data(), 0);
+            return Ok(Some(reader.read_string()?.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// The 0-based source lines the segments of the single generated line of a
+/// source map point to
+fn mapped_lines(mappings: &str) -> anyhow::Result<Vec<usize>> {
+    const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut lines = vec![];
+    let mut line = 0;
+    for segment in mappings.split(',') {
+        let mut fields = vec![];
+        let (mut value, mut shift) = (0_i64, 0);
+        for digit in segment.bytes() {
+            let digit = DIGITS
+                .iter()
+                .position(|known| *known == digit)
+                .ok_or_else(|| anyhow::anyhow!("{segment} is not base64"))?;
+            let digit = i64::try_from(digit)?;
+            value |= (digit & 0b1_1111) << shift;
+            shift += 5;
+            if digit & 0b10_0000 == 0 {
+                fields.push(if value & 1 == 1 {
+                    -(value >> 1)
+                } else {
+                    value >> 1
+                });
+                (value, shift) = (0, 0);
+            }
+        }
+        // Segments of unmapped code only have the generated column
+        if let [_, _, line_delta, _] = fields[..] {
+            line += line_delta;
+            lines.push(usize::try_from(line)?);
+        }
+    }
+    Ok(lines)
+}
+
+#[test]
+fn no_source_map_by_default() -> anyhow::Result<()> {
+    let config = config_for(
+        Debugging::Enabled,
+        SourceMap::Disabled,
+        Toolchain::default(),
+    );
+    let compilation = Compiler::compile(&config)?;
+
+    assert!(compilation.source_map().is_none());
+    assert!(source_mapping_url(compilation.wasm())?.is_none());
+    Ok(())
+}
+
+#[test]
+fn fac_is_mapped_to_its_source() -> anyhow::Result<()> {
+    let config = config_for(
+        Debugging::Enabled,
+        SourceMap::Separate,
+        Toolchain::default(),
+    );
+    let compilation = Compiler::compile(&config)?;
+    let source_map: serde_json::Value = serde_json::from_str(compilation.source_map().unwrap())?;
+
+    assert_eq!(source_map["version"], 3);
+    assert_eq!(source_map["sources"], serde_json::json!(["fac.rs"]));
+    assert_eq!(
+        source_map["sourcesContent"],
+        serde_json::json!([FAC_SOURCE])
+    );
+    assert!(!source_map["mappings"].as_str().unwrap().is_empty());
+    assert!(source_mapping_url(compilation.wasm())?.is_none());
+    Ok(())
+}
+
+#[test]
+fn segments_point_to_lines_of_fac() -> anyhow::Result<()> {
+    let config = config_for(
+        Debugging::Enabled,
+        SourceMap::Separate,
+        Toolchain::default(),
+    );
+    let compilation = Compiler::compile(&config)?;
+    let source_map: serde_json::Value = serde_json::from_str(compilation.source_map().unwrap())?;
+    let lines = mapped_lines(source_map["mappings"].as_str().unwrap())?;
+
+    let fac: Vec<&str> = FAC_SOURCE.lines().collect();
+    assert!(!lines.is_empty());
+    for line in &lines {
+        assert!(fac.get(*line).is_some_and(|text| !text.trim().is_empty()));
+    }
+    // The recursive call, `return n * fac(n - 1);`
+    assert!(lines.contains(&5), "{lines:?}");
+    Ok(())
+}
+
+#[test]
+fn linked_source_maps_are_referred_to() -> anyhow::Result<()> {
+    let source_map = SourceMap::Linked("http://localhost/fac.wasm.map".into());
+    let compilation = Compiler::compile(&config_for(
+        Debugging::Enabled,
+        source_map,
+        Toolchain::default(),
+    ))?;
+
+    assert!(compilation.source_map().is_some());
+    assert_eq!(
+        source_mapping_url(compilation.wasm())?.as_deref(),
+        Some("http://localhost/fac.wasm.map")
+    );
+    Ok(())
+}
+
+#[test]
+fn embedded_source_maps_are_data_urls() -> anyhow::Result<()> {
+    let config = config_for(
+        Debugging::Enabled,
+        SourceMap::Embedded,
+        Toolchain::default(),
+    );
+    let compilation = Compiler::compile(&config)?;
+
+    let url = source_mapping_url(compilation.wasm())?.unwrap();
+    let encoded = url
+        .strip_prefix("data:application/json;charset=utf-8;base64,")
+        .unwrap();
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+    assert_eq!(
+        String::from_utf8(decoded)?,
+        compilation.source_map().unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn source_maps_require_debugging() {
+    let config = config_for(
+        Debugging::Disabled,
+        SourceMap::Separate,
+        Toolchain::default(),
+    );
+
+    assert!(matches!(
+        Compiler::compile(&config),
+        Err(Error::SourceMapWithoutDebugging)
+    ));
+}
+
+#[cfg(unix)]
+mod fake_rustc {
+    use rustc_to_wasm_compiler::configuration::CrateType;
+    use rustc_to_wasm_compiler::error::SymbolicationError;
+
+    use super::*;
+
+    #[test]
+    fn modules_without_dwarf_cannot_be_mapped() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let module = wat::parse_str(r#"(module (func (export "fac")))"#)?;
+        let rustc = fakes::producing_rustc(directory.path(), &module)?;
+        let config = config_for(
+            Debugging::Enabled,
+            SourceMap::Separate,
+            Toolchain::binary(rustc),
+        );
+
+        assert!(matches!(
+            Compiler::compile(&config),
+            Err(Error::SourceMapUnavailable(SymbolicationError::NoDebugInfo))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn libraries_are_not_mapped() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), b"!<arch>\n")?;
+        let config = fakes::fac_compiled_by(rustc)
+            .debugging(Debugging::Enabled)
+            .crate_type(CrateType::Rlib)
+            .source_map(SourceMap::Embedded)
+            .build();
+
+        let compilation = Compiler::compile(&config)?;
+        assert!(compilation.source_map().is_none());
+        assert_eq!(compilation.wasm(), b"!<arch>\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use rustc_to_wasm_compiler::configuration::{Configuration, Debugging};
+use rustc_to_wasm_compiler::error::SymbolicationError;
+use rustc_to_wasm_compiler::symbolication::Symbolizer;
+
+use crate::common::fac_config;
+
+const TRAP_SOURCE: &str = r#"#[inline(always)]
+fn fail() {
+    core::arch::wasm32::unreachable()
+}
+
+#[no_mangle]
+pub extern "C" fn trap() {
+    fail()
+}
+"#;
+
+fn config_for(debugging: Debugging) -> Configuration {
+    fac_config()
+        .debugging(debugging)
+        .source(TRAP_SOURCE.into())
+        .build()
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn traps_are_traced_to_the_source() -> anyhow::Result<()> {
+    use std::path::Path;
+
+    use rustc_to_wasm_compiler::Compiler;
+    use rustc_to_wasm_compiler::error::RuntimeError;
+    use rustc_to_wasm_compiler::runtime::ExecutionLimits;
+    use rustc_to_wasm_compiler::runtime::wasmtime::Trap;
+
+    let compilation = Compiler::compile(&config_for(Debugging::Enabled))?;
+    let module = compilation.compiled_module(ExecutionLimits::new())?;
+    let Err(RuntimeError::Trap(Trap::UnreachableCodeReached, offsets)) =
+        module.call::<(), ()>("trap", ())
+    else {
+        panic!("expected a trap");
+    };
+
+    let frames = compilation.symbolizer()?.backtrace(&offsets)?;
+    // Frames in the standard library, if any, precede those of the source
+    let in_source: Vec<_> = frames
+        .iter()
+        .filter(|frame| frame.file.as_deref() == Some(Path::new("rustc-to-wasm-source.rs")))
+        .collect();
+
+    let fail = in_source[0];
+    assert!(fail.function.as_ref().unwrap().ends_with("fail"));
+    assert_eq!((fail.line, fail.column), (Some(3), Some(5)));
+    assert!(fail.inlined);
+
+    let trap = in_source[1];
+    assert!(trap.function.as_ref().unwrap().ends_with("trap"));
+    assert_eq!(trap.line, Some(8));
+    assert!(!trap.inlined);
+    Ok(())
+}
+
+#[test]
+fn symbolization_requires_debug_info() -> anyhow::Result<()> {
+    use rustc_to_wasm_compiler::Compiler;
+
+    let compilation = Compiler::compile(&config_for(Debugging::Disabled))?;
+    assert!(matches!(
+        compilation.symbolizer(),
+        Err(SymbolicationError::NoDebugInfo)
+    ));
+    Ok(())
+}
+
+#[test]
+fn modules_without_dwarf_are_rejected() -> anyhow::Result<()> {
+    let wasm = wat::parse_str(r#"(module (func (export "trap") unreachable))"#)?;
+
+    assert!(matches!(
+        Symbolizer::new(&wasm),
+        Err(SymbolicationError::NoDebugInfo)
+    ));
+    assert!(matches!(
+        Symbolizer::new(b"not a module"),
+        Err(SymbolicationError::Unreadable(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Profile, TargetCpu, TargetFeature, TargetFeatures};
+use wasmparser::{Operator, Parser, Payload};
+
+use crate::common::fac_config;
+
+const SUM_SOURCE: &str = r#"
+    #[no_mangle]
+    pub extern "C" fn sum(ptr: *const i32, len: usize) -> i32 {
+        let xs = unsafe { core::slice::from_raw_parts(ptr, len) };
+        xs.iter().fold(0, |acc, x| acc.wrapping_add(*x))
+    }
+"#;
+
+const SIGN_EXTEND_SOURCE: &str = r#"
+    #[no_mangle]
+    pub extern "C" fn sign_extend(n: i32) -> i32 {
+        n as i8 as i32
+    }
+"#;
+
+fn compile_with(source: &str, target_features: TargetFeatures) -> Vec<u8> {
+    let config = fac_config()
+        .profile(Profile::O3)
+        .source(source.into())
+        .target_features(target_features)
+        .build();
+
+    Compiler::compile(&config).unwrap().into_wasm()
+}
+
+fn uses_operator(wasm: &[u8], matches: impl Fn(&Operator) -> bool) -> bool {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CodeSectionEntry(body) = payload.unwrap() {
+            let mut operators = body.get_operators_reader().unwrap();
+            while !operators.eof() {
+                if matches(&operators.read().unwrap()) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+#[test]
+fn simd128_toggles_vector_instructions() {
+    let is_vector_add = |operator: &Operator| matches!(operator, Operator::I32x4Add);
+
+    let enabled = TargetFeatures::new().enable(TargetFeature::Simd128);
+    assert!(uses_operator(
+        &compile_with(SUM_SOURCE, enabled),
+        is_vector_add
+    ));
+
+    let disabled = TargetFeatures::new().disable(TargetFeature::Simd128);
+    assert!(!uses_operator(
+        &compile_with(SUM_SOURCE, disabled),
+        is_vector_add
+    ));
+}
+
+#[test]
+fn sign_ext_toggles_extend_instructions() {
+    let is_extend = |operator: &Operator| matches!(operator, Operator::I32Extend8S);
+
+    // Only the flag brings the instructions back on a CPU without them
+    let enabled = TargetFeatures::new()
+        .cpu(TargetCpu::Mvp)
+        .enable(TargetFeature::SignExt);
+    assert!(uses_operator(
+        &compile_with(SIGN_EXTEND_SOURCE, enabled),
+        is_extend
+    ));
+
+    let mvp = TargetFeatures::new().cpu(TargetCpu::Mvp);
+    assert!(!uses_operator(
+        &compile_with(SIGN_EXTEND_SOURCE, mvp),
+        is_extend
+    ));
+}
+
+#[test]
+fn enable_and_disable_override_each_other() {
+    let features = TargetFeatures::new()
+        .cpu(TargetCpu::BleedingEdge)
+        .enable(TargetFeature::TailCall)
+        .disable(TargetFeature::TailCall)
+        .disable(TargetFeature::Atomics)
+        .enable(TargetFeature::Atomics);
+
+    assert_eq!(features.selected_cpu(), &TargetCpu::BleedingEdge);
+    assert!(features.is_disabled(TargetFeature::TailCall));
+    assert!(!features.is_enabled(TargetFeature::TailCall));
+    assert!(features.is_enabled(TargetFeature::Atomics));
+    assert!(!features.is_disabled(TargetFeature::Atomics));
+}
@@ -0,0 +1,150 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{
+    CrateType, Debugging, Filename, Profile, Source, StackSize, Target,
+};
+use rustc_to_wasm_compiler::configuration_builder::ConfigurationBuilder;
+
+use crate::common::fac_config;
+
+/// The settings for `source` on the default target
+fn config_for(
+    source: &str,
+) -> ConfigurationBuilder<Profile, Debugging, StackSize, Source, Filename> {
+    fac_config().source(source.into())
+}
+
+#[test]
+fn default_target() {
+    let config = fac_config().build();
+
+    assert_eq!(config.target(), &Target::Wasm32UnknownUnknown);
+    assert_eq!(config.crate_type(), CrateType::Cdylib);
+}
+
+#[test]
+fn crate_type_defaults_per_target() {
+    let wasi = fac_config().target(Target::Wasm32Wasip1).build();
+    assert_eq!(wasi.crate_type(), CrateType::Bin);
+
+    let overridden = fac_config()
+        .target(Target::Wasm32Wasip1)
+        .crate_type(CrateType::Cdylib)
+        .build();
+    assert_eq!(overridden.crate_type(), CrateType::Cdylib);
+}
+
+#[test]
+fn wasip1_library() -> anyhow::Result<()> {
+    let config = fac_config()
+        .target(Target::Wasm32Wasip1)
+        .crate_type(CrateType::Cdylib)
+        .build();
+
+    let compilation = Compiler::compile(&config)?;
+    assert_eq!(compilation.crate_type(), CrateType::Cdylib);
+    assert_eq!(&compilation.wasm()[0..4], b"\0asm");
+    Ok(())
+}
+
+#[test]
+fn rlib_archive() -> anyhow::Result<()> {
+    let source = "
+        pub fn double(n: i32) -> i32 {
+            n * 2
+        }
+    ";
+
+    let config = config_for(source)
+        .filename(Filename::Configured("helper.rs".into()))
+        .crate_type(CrateType::Rlib)
+        .build();
+
+    let compilation = Compiler::compile(&config)?;
+    assert_eq!(compilation.crate_type(), CrateType::Rlib);
+    assert!(compilation.wasm().starts_with(b"!<arch>\n"));
+    Ok(())
+}
+
+#[test]
+fn wasip1_println() -> anyhow::Result<()> {
+    use wasmtime::{Engine, Linker, Module, Store};
+    use wasmtime_wasi::WasiCtxBuilder;
+    use wasmtime_wasi::p1::{self, WasiP1Ctx};
+    use wasmtime_wasi::p2::pipe::MemoryOutputPipe;
+
+    let source = r#"
+        fn main() {
+            println!("Hello, {}!", "WASI");
+        }
+    "#;
+
+    let config = config_for(source).target(Target::Wasm32Wasip1).build();
+    let compilation = Compiler::compile(&config)?;
+    assert_eq!(compilation.crate_type(), CrateType::Bin);
+    let wasm = compilation.into_wasm();
+
+    let stdout = MemoryOutputPipe::new(1024);
+    let engine = Engine::default();
+    let module = Module::from_binary(&engine, &wasm)?;
+    let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+    p1::add_to_linker_sync(&mut linker, |ctx| ctx)?;
+    let wasi_ctx = WasiCtxBuilder::new().stdout(stdout.clone()).build_p1();
+    let mut store = Store::new(&engine, wasi_ctx);
+    let instance = linker.instantiate(&mut store, &module)?;
+    instance
+        .get_typed_func::<(), ()>(&mut store, "_start")?
+        .call(&mut store, ())?;
+
+    assert_eq!(&stdout.contents()[..], b"Hello, WASI!\n");
+    Ok(())
+}
+
+#[test]
+fn wasip2_produces_component() -> anyhow::Result<()> {
+    let source = r#"
+        fn main() {
+            println!("Hello, components!");
+        }
+    "#;
+
+    let config = config_for(source).target(Target::Wasm32Wasip2).build();
+    let wasm = Compiler::compile(&config)?.into_wasm();
+
+    // Components share the magic bytes of core modules, but carry layer `1`
+    assert_eq!(&wasm[0..4], b"\0asm");
+    assert_eq!(&wasm[6..8], &[0x01, 0x00]);
+    Ok(())
+}
+
+#[test]
+fn wasm32v1_none_no_std() -> anyhow::Result<()> {
+    use wasmtime::{Engine, Instance, Module, Store};
+
+    let source = r#"
+        #![no_std]
+
+        #[panic_handler]
+        fn panic(_: &core::panic::PanicInfo) -> ! {
+            loop {}
+        }
+
+        #[no_mangle]
+        pub extern "C" fn fac(n: i32) -> i32 {
+            if n == 0 { 1 } else { n * fac(n - 1) }
+        }
+    "#;
+
+    let config = config_for(source).target(Target::Wasm32v1None).build();
+    let wasm = Compiler::compile(&config)?.into_wasm();
+
+    let engine = Engine::default();
+    let module = Module::from_binary(&engine, &wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let outcome = instance
+        .get_typed_func::<i32, i32>(&mut store, "fac")?
+        .call(&mut store, 5)?;
+
+    assert_eq!(outcome, 120);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::Target;
+use rustc_to_wasm_compiler::error::{Error, VersionError};
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+
+use crate::common::fac_config;
+
+fn default_sysroot() -> PathBuf {
+    let output = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .unwrap();
+    PathBuf::from(String::from_utf8(output.stdout).unwrap().trim())
+}
+
+#[test]
+fn explicit_binary_reports_its_version() -> anyhow::Result<()> {
+    let rustc = default_sysroot().join("bin").join("rustc");
+    let toolchain = Toolchain::binary(rustc);
+
+    assert_eq!(Compiler::version_of(&toolchain)?, Compiler::version()?);
+    Ok(())
+}
+
+#[test]
+fn missing_binary_is_reported() {
+    let toolchain = Toolchain::binary("/nonexistent/bin/rustc".into());
+    assert!(matches!(
+        Compiler::version_of(&toolchain),
+        Err(VersionError::IO(_))
+    ));
+}
+
+#[test]
+fn unknown_rustup_toolchain_is_reported() {
+    let toolchain = Toolchain::rustup("nonexistent-toolchain");
+    assert!(matches!(
+        Compiler::version_of(&toolchain),
+        Err(VersionError::InvocationNoSuccess(_))
+    ));
+}
+
+#[test]
+fn compilation_honors_sysroot() -> anyhow::Result<()> {
+    let default = fac_config()
+        .toolchain(Toolchain::from_path().sysroot(default_sysroot()))
+        .build();
+    Compiler::compile(&default)?;
+
+    let empty_sysroot = tempfile::TempDir::new()?;
+    let bogus = fac_config()
+        .toolchain(Toolchain::from_path().sysroot(empty_sysroot.path().into()))
+        .build();
+    assert!(matches!(
+        Compiler::compile(&bogus),
+        Err(Error::Unsuccesful(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn toolchain_info() -> anyhow::Result<()> {
+    let info = Compiler::toolchain_info()?;
+
+    assert_eq!(info.release, Compiler::version()?);
+    assert_eq!(info.sysroot, default_sysroot());
+    assert!(!info.host.is_empty());
+    assert!(info.llvm_version.is_some());
+    // The test suite itself compiles for the default target
+    assert!(info.has_target(Target::Wasm32UnknownUnknown));
+    Ok(())
+}
+
+#[test]
+fn toolchain_info_honors_sysroot() -> anyhow::Result<()> {
+    let empty_sysroot = tempfile::TempDir::new()?;
+    let toolchain = Toolchain::from_path().sysroot(empty_sysroot.path().into());
+    let info = Compiler::toolchain_info_of(&toolchain)?;
+
+    assert_eq!(info.sysroot, empty_sysroot.path());
+    assert!(info.wasm_targets.is_empty());
+    assert!(!info.has_target(Target::Wasm32UnknownUnknown));
+    Ok(())
+}
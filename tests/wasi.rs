@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::Target;
+use rustc_to_wasm_compiler::error::RuntimeError;
+use rustc_to_wasm_compiler::runtime::wasmtime::Trap;
+use rustc_to_wasm_compiler::runtime::{
+    CompiledModule, DEFAULT_OUTPUT_LIMIT, ExecutionLimits, WasiOutput, WasiRun, WasiStatus,
+};
+
+use crate::common::fac_config;
+
+/// Writes `hello\n` to stdout `TIMES` times and `oops\n` to stderr once
+const HELLO: &str = r#"
+    (module
+      (import "wasi_snapshot_preview1" "fd_write"
+        (func $fd_write (param i32 i32 i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 100) "hello\n")
+      (data (i32.const 200) "oops\n")
+      (global $times i32 (i32.const TIMES))
+      (func $write (param $fd i32) (param $data i32) (param $length i32)
+        (i32.store (i32.const 0) (local.get $data))
+        (i32.store (i32.const 4) (local.get $length))
+        (drop (call $fd_write (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8))))
+      (func (export "_start")
+        (local $i i32)
+        (block $done
+          (loop $next
+            (br_if $done (i32.ge_s (local.get $i) (global.get $times)))
+            (call $write (i32.const 1) (i32.const 100) (i32.const 6))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $next)))
+        (call $write (i32.const 2) (i32.const 200) (i32.const 5))))
+"#;
+
+/// Copies stdin, or the file `input.txt` of the first preopened directory
+/// when there is one, to stdout
+const CAT: &str = r#"
+    (module
+      (import "wasi_snapshot_preview1" "fd_read"
+        (func $fd_read (param i32 i32 i32 i32) (result i32)))
+      (import "wasi_snapshot_preview1" "fd_write"
+        (func $fd_write (param i32 i32 i32 i32) (result i32)))
+      (import "wasi_snapshot_preview1" "path_open"
+        (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 300) "input.txt")
+      (func (export "_start")
+        (local $fd i32)
+        ;; The first preopened directory is fd 3, its file is opened as fd 4
+        (if (i32.eqz (call $path_open (i32.const 3) (i32.const 0) (i32.const 300) (i32.const 9)
+                                      (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0)
+                                      (i32.const 16)))
+          (then (local.set $fd (i32.load (i32.const 16)))))
+        (i32.store (i32.const 0) (i32.const 1000))
+        (i32.store (i32.const 4) (i32.const 1000))
+        (drop (call $fd_read (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8)))
+        (i32.store (i32.const 4) (i32.load (i32.const 8)))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#;
+
+/// Exits with ten times the number of arguments plus the number of
+/// environment variables
+const COUNT: &str = r#"
+    (module
+      (import "wasi_snapshot_preview1" "args_sizes_get"
+        (func $args_sizes_get (param i32 i32) (result i32)))
+      (import "wasi_snapshot_preview1" "environ_sizes_get"
+        (func $environ_sizes_get (param i32 i32) (result i32)))
+      (import "wasi_snapshot_preview1" "proc_exit"
+        (func $proc_exit (param i32)))
+      (memory (export "memory") 1)
+      (func (export "_start")
+        (drop (call $args_sizes_get (i32.const 0) (i32.const 4)))
+        (drop (call $environ_sizes_get (i32.const 8) (i32.const 12)))
+        (call $proc_exit
+          (i32.add (i32.mul (i32.load (i32.const 0)) (i32.const 10))
+                   (i32.load (i32.const 8))))))
+"#;
+
+fn hello(times: u32) -> String {
+    HELLO.replace("TIMES", &times.to_string())
+}
+
+fn run(wat: &str, limits: ExecutionLimits, run: &WasiRun) -> anyhow::Result<WasiOutput> {
+    Ok(CompiledModule::new(&wat::parse_str(wat)?, limits)?.run_wasi(run)?)
+}
+
+#[test]
+fn main_is_run() -> anyhow::Result<()> {
+    let source = r#"
+        fn main() {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).unwrap();
+            let name = std::env::args().nth(1).unwrap();
+            println!("{} {name}", line.trim());
+            eprintln!("done");
+            std::process::exit(7);
+        }
+    "#;
+    let config = fac_config()
+        .source(source.into())
+        .target(Target::Wasm32Wasip1)
+        .build();
+    let module = Compiler::compile(&config)?.compiled_module(ExecutionLimits::new())?;
+
+    let output = module.run_wasi(
+        &WasiRun::new()
+            .arg("greet")
+            .arg("WASI")
+            .stdin(b"Hello\n".to_vec()),
+    )?;
+    assert_eq!(output.status, WasiStatus::Exited(7));
+    assert_eq!(output.stdout, b"Hello WASI\n");
+    assert_eq!(output.stderr, b"done\n");
+    Ok(())
+}
+
+#[test]
+fn output_is_captured() -> anyhow::Result<()> {
+    let output = run(&hello(1), ExecutionLimits::new(), &WasiRun::new())?;
+
+    assert_eq!(output.status, WasiStatus::Exited(0));
+    assert_eq!(output.stdout, b"hello\n");
+    assert_eq!(output.stderr, b"oops\n");
+    Ok(())
+}
+
+#[test]
+fn exit_code_args_and_env_are_passed() -> anyhow::Result<()> {
+    let output = run(COUNT, ExecutionLimits::new(), &WasiRun::new())?;
+    assert_eq!(output.status, WasiStatus::Exited(0));
+
+    let arguments = WasiRun::new()
+        .arg("count")
+        .arg("--verbose")
+        .env("LANG", "C")
+        .env("HOME", "/");
+    let output = run(COUNT, ExecutionLimits::new(), &arguments)?;
+    assert_eq!(output.status, WasiStatus::Exited(22));
+    Ok(())
+}
+
+#[test]
+fn stdin_is_read() -> anyhow::Result<()> {
+    let input = WasiRun::new().stdin(b"from stdin".to_vec());
+    let output = run(CAT, ExecutionLimits::new(), &input)?;
+
+    assert_eq!(output.stdout, b"from stdin");
+    Ok(())
+}
+
+#[test]
+fn preopened_files_are_read() -> anyhow::Result<()> {
+    let files = BTreeMap::from([(PathBuf::from("input.txt"), b"from a file".to_vec())]);
+    let input = WasiRun::new()
+        .preopen("/data", files)
+        .stdin(b"from stdin".to_vec());
+    let output = run(CAT, ExecutionLimits::new(), &input)?;
+
+    assert_eq!(output.stdout, b"from a file");
+    Ok(())
+}
+
+#[test]
+fn preopened_files_stay_inside() {
+    let files = BTreeMap::from([(PathBuf::from("../escape.txt"), vec![])]);
+    let result = run(
+        CAT,
+        ExecutionLimits::new(),
+        &WasiRun::new().preopen("/", files),
+    );
+
+    assert!(result.is_err_and(|error| matches!(
+        error.downcast_ref(),
+        Some(RuntimeError::InvalidPreopenPath(_))
+    )));
+}
+
+#[test]
+fn output_is_limited() -> anyhow::Result<()> {
+    // Exactly at the limit is fine
+    let output = run(
+        &hello(1),
+        ExecutionLimits::new(),
+        &WasiRun::new().output_limit(6),
+    )?;
+    assert_eq!(output.status, WasiStatus::Exited(0));
+    assert_eq!(output.stdout, b"hello\n");
+
+    let output = run(
+        &hello(3),
+        ExecutionLimits::new(),
+        &WasiRun::new().output_limit(10),
+    )?;
+    assert_eq!(output.status, WasiStatus::OutputLimitExceeded);
+    assert_eq!(output.stdout, b"hello\nhell");
+    Ok(())
+}
+
+#[test]
+fn output_is_limited_by_default() -> anyhow::Result<()> {
+    let times = u32::try_from(DEFAULT_OUTPUT_LIMIT / 6 + 1)?;
+    let output = run(&hello(times), ExecutionLimits::new(), &WasiRun::new())?;
+
+    assert_eq!(output.status, WasiStatus::OutputLimitExceeded);
+    assert_eq!(output.stdout.len(), DEFAULT_OUTPUT_LIMIT);
+    Ok(())
+}
+
+#[test]
+fn fuel_is_limited() -> anyhow::Result<()> {
+    let spin = r#"(module (func (export "_start") (loop $forever (br $forever))))"#;
+    let output = run(spin, ExecutionLimits::new().fuel(100_000), &WasiRun::new())?;
+
+    assert!(matches!(
+        output.status,
+        WasiStatus::Trapped(Trap::OutOfFuel, ref offsets) if !offsets.is_empty()
+    ));
+    Ok(())
+}
+
+#[test]
+fn commands_need_a_start() {
+    let result = run("(module)", ExecutionLimits::new(), &WasiRun::new());
+
+    assert!(result.is_err_and(|error| matches!(
+        error.downcast_ref(),
+        Some(RuntimeError::UnknownExport(name)) if name == "_start"
+    )));
+}
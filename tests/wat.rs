@@ -0,0 +1,84 @@
+use rustc_to_wasm_compiler::Compiler;
+use rustc_to_wasm_compiler::configuration::{Configuration, Wat};
+use rustc_to_wasm_compiler::toolchain::Toolchain;
+
+use crate::common::fac_config;
+#[cfg(unix)]
+use crate::fakes;
+
+fn config_for(wat: Wat, toolchain: Toolchain) -> Configuration {
+    fac_config().toolchain(toolchain).wat(wat).build()
+}
+
+#[test]
+fn no_wat_by_default() -> anyhow::Result<()> {
+    let compilation = Compiler::compile(&config_for(Wat::Disabled, Toolchain::default()))?;
+    assert!(compilation.wat().is_none());
+    Ok(())
+}
+
+#[test]
+fn fac_is_rendered_with_names() -> anyhow::Result<()> {
+    let compilation = Compiler::compile(&config_for(Wat::Enabled, Toolchain::default()))?;
+    let wat = compilation.wat().unwrap();
+
+    assert!(wat.starts_with("(module"));
+    assert!(wat.contains("(func $fac"));
+    assert!(wat.contains(r#"(export "fac" (func $fac))"#));
+    Ok(())
+}
+
+#[cfg(unix)]
+mod fake_rustc {
+    use rustc_to_wasm_compiler::configuration::CrateType;
+    use rustc_to_wasm_compiler::error::Error;
+
+    use super::*;
+
+    const ADD: &str = r#"
+        (module
+          (func $add (export "add") (param $lhs i32) (param $rhs i32) (result i32)
+            local.get $lhs
+            local.get $rhs
+            i32.add))
+    "#;
+
+    #[test]
+    fn instructions_are_folded_and_named() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), &wat::parse_str(ADD)?)?;
+        let compilation = Compiler::compile(&config_for(Wat::Enabled, Toolchain::binary(rustc)))?;
+        let wat = compilation.wat().unwrap();
+
+        assert!(wat.contains("(func $add"));
+        assert!(wat.contains("(param $lhs i32)"));
+        assert!(wat.contains("(i32.add\n"));
+        assert!(wat.contains("(local.get $lhs)"));
+        Ok(())
+    }
+
+    #[test]
+    fn libraries_are_not_rendered() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), b"!<arch>\n")?;
+        let config = fakes::fac_compiled_by(rustc)
+            .crate_type(CrateType::Rlib)
+            .wat(Wat::Enabled)
+            .build();
+
+        assert!(Compiler::compile(&config)?.wat().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_modules_are_reported() -> anyhow::Result<()> {
+        let directory = tempfile::TempDir::new()?;
+        let rustc = fakes::producing_rustc(directory.path(), b"not a module")?;
+
+        assert!(matches!(
+            Compiler::compile(&config_for(Wat::Enabled, Toolchain::binary(rustc))),
+            Err(Error::WatUnprintable(_))
+        ));
+        Ok(())
+    }
+}